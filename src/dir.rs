@@ -0,0 +1,86 @@
+use std::io;
+use std::fs;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use {Builder, default_temp_dir, cleanup_failed};
+
+#[cfg(unix)]
+pub fn create(path: PathBuf) -> io::Result<TempDir> {
+    use std::os::unix::fs::DirBuilderExt;
+    // Like temporary files, only the owner may access the directory (`create_dir` would respect
+    // the umask, typically leaving it world-readable).
+    fs::DirBuilder::new().mode(0o700).create(&path).map(|_| TempDir(Some(path)))
+}
+
+#[cfg(windows)]
+pub fn create(path: PathBuf) -> io::Result<TempDir> {
+    fs::create_dir(&path).map(|_| TempDir(Some(path)))
+}
+
+/// A directory in the filesystem that is automatically deleted when it goes out of scope.
+///
+/// The directory and everything inside it is removed when the `TempDir` is dropped. As with
+/// `NamedTempFile`, rust doesn't guarantee that the destructor will ever run so the directory may
+/// be leaked on abort, early exit, etc.
+pub struct TempDir(Option<PathBuf>);
+
+impl TempDir {
+    /// Create a new temporary directory.
+    #[inline]
+    pub fn new() -> io::Result<TempDir> {
+        Self::new_in(&default_temp_dir())
+    }
+
+    /// Create a new temporary directory inside the specified directory.
+    #[inline]
+    pub fn new_in<P: AsRef<Path>>(dir: P) -> io::Result<TempDir> {
+        Builder::new().tempdir_in(dir)
+    }
+
+    /// Get the temporary directory's path.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        self.0.as_ref().unwrap()
+    }
+
+    /// Recursively delete the temporary directory.
+    ///
+    /// Use this if you want to detect errors in deleting the directory.
+    #[inline]
+    pub fn close(mut self) -> io::Result<()> {
+        fs::remove_dir_all(self.0.take().unwrap())
+    }
+
+    /// Extract the path to the temporary directory. Calling this will prevent the temporary
+    /// directory from being automatically deleted.
+    #[inline]
+    pub fn into_path(mut self) -> PathBuf {
+        self.0.take().unwrap()
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl fmt::Debug for TempDir {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TempDir({:?})", self.path())
+    }
+}
+
+impl Drop for TempDir {
+    #[inline]
+    fn drop(&mut self) {
+        if let Some(path) = self.0.take() {
+            if let Err(e) = fs::remove_dir_all(&path) {
+                cleanup_failed(&path, &e);
+            }
+        }
+    }
+}
@@ -3,30 +3,205 @@ use std::io;
 use std::os::unix::io::{AsRawFd, FromRawFd};
 use std::fs::{File, OpenOptions};
 use std::path::Path;
-use ::util::cstr;
-use super::unix_common::O_CLOEXEC;
+use std::ffi::CString;
+use ::util::{cstr, exchange_by_rename};
+use super::unix_common::flags;
+use ::Builder;
 use super::unix_common::create as create_unix;
-pub use super::unix_common::create_named;
+pub use super::unix_common::{create_named, create_with_options, persist_noclobber, crosses_devices,
+                             sync_dir, same_file, same_file_at};
 
 const O_TMPFILE: libc::c_int = 0o20200000;
 
-pub fn create(dir: &Path) -> io::Result<File> {
+const MFD_CLOEXEC: libc::c_uint = 0x0001;
+const MFD_ALLOW_SEALING: libc::c_uint = 0x0002;
+const F_ADD_SEALS: c_int = 1033;
+const F_GET_SEALS: c_int = 1034;
+const AT_FDCWD: c_int = -100;
+const RENAME_EXCHANGE: libc::c_uint = 0x0002;
+const AT_SYMLINK_FOLLOW: c_int = 0x400;
+
+extern "C" {
+    fn fallocate64(fd: c_int, mode: c_int, offset: libc::off64_t, len: libc::off64_t) -> c_int;
+    fn memfd_create(name: *const libc::c_char, flags: libc::c_uint) -> c_int;
+    fn getxattr(path: *const libc::c_char, name: *const libc::c_char, value: *mut libc::c_void,
+                size: libc::size_t) -> libc::ssize_t;
+    fn fsetxattr(fd: c_int, name: *const libc::c_char, value: *const libc::c_void,
+                 size: libc::size_t, flags: c_int) -> c_int;
+    fn linkat(olddirfd: c_int, oldpath: *const libc::c_char,
+              newdirfd: c_int, newpath: *const libc::c_char, flags: c_int) -> c_int;
+    fn renameat2(olddirfd: c_int, oldpath: *const libc::c_char,
+                 newdirfd: c_int, newpath: *const libc::c_char, flags: libc::c_uint) -> c_int;
+}
+
+pub fn create_memfd(name: &str) -> io::Result<File> {
+    let name = try!(CString::new(name).map_err(|_| {
+        io::Error::new(io::ErrorKind::InvalidInput, "memfd name contained a null")
+    }));
+    match unsafe { memfd_create(name.as_ptr(), MFD_CLOEXEC | MFD_ALLOW_SEALING) } {
+        -1 => Err(io::Error::last_os_error()),
+        fd => Ok(unsafe { FromRawFd::from_raw_fd(fd) }),
+    }
+}
+
+pub fn add_seals(f: &File, seals: u32) -> io::Result<()> {
+    match unsafe { libc::fcntl(f.as_raw_fd(), F_ADD_SEALS, seals as c_int) } {
+        -1 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+pub fn get_seals(f: &File) -> io::Result<u32> {
+    match unsafe { libc::fcntl(f.as_raw_fd(), F_GET_SEALS) } {
+        -1 => Err(io::Error::last_os_error()),
+        seals => Ok(seals as u32),
+    }
+}
+
+pub fn exchange_paths(a: &Path, b: &Path) -> io::Result<()> {
+    let a_c = try!(cstr(a));
+    let b_c = try!(cstr(b));
+    match unsafe { renameat2(AT_FDCWD, a_c.as_ptr(), AT_FDCWD, b_c.as_ptr(), RENAME_EXCHANGE) } {
+        0 => Ok(()),
+        _ => {
+            let err = io::Error::last_os_error();
+            match err.raw_os_error() {
+                // The kernel (< 3.15) doesn't support renameat2.
+                Some(libc::ENOSYS) => exchange_by_rename(a, b),
+                // Either the filesystem doesn't support RENAME_EXCHANGE, or one path is an
+                // ancestor of the other. Only fall back in the former case.
+                Some(libc::EINVAL) if !is_nested(a, b) => exchange_by_rename(a, b),
+                _ => Err(err),
+            }
+        },
+    }
+}
+
+fn is_nested(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a.starts_with(&b) || b.starts_with(&a),
+        // Can't tell; don't risk renaming things around.
+        _ => true,
+    }
+}
+
+pub fn create_anonymous(_dir: &Path) -> io::Result<File> {
+    create_memfd("tempfile")
+}
+
+pub fn copy_security_context(f: &File, reference: &Path) -> io::Result<()> {
+    let reference = try!(cstr(reference));
+    let name = b"security.selinux\0".as_ptr() as *const libc::c_char;
+    let mut context = Vec::new();
+    let len = loop {
+        // Passing a size of 0 returns the size of the value without reading it.
+        let len = unsafe {
+            getxattr(reference.as_ptr(), name, context.as_mut_ptr() as *mut libc::c_void,
+                     context.len() as libc::size_t)
+        };
+        if len < 0 {
+            let err = io::Error::last_os_error();
+            match err.raw_os_error() {
+                // The label grew between the two calls; query its size again.
+                Some(libc::ERANGE) => {
+                    context.clear();
+                    continue;
+                },
+                // No label to copy: SELinux is disabled or the filesystem doesn't support labels.
+                Some(libc::ENODATA) | Some(libc::EOPNOTSUPP) => return Ok(()),
+                _ => return Err(err),
+            }
+        }
+        let len = len as usize;
+        if context.is_empty() && len > 0 {
+            context.resize(len, 0);
+            continue;
+        }
+        break len;
+    };
+    match unsafe {
+        fsetxattr(f.as_raw_fd(), name, context.as_ptr() as *const libc::c_void,
+                  len as libc::size_t, 0)
+    } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+pub fn link_at(f: &File, path: &Path) -> io::Result<()> {
+    // Linking through /proc with AT_SYMLINK_FOLLOW (unlike AT_EMPTY_PATH) needs no capabilities.
+    let proc_path = try!(cstr(Path::new(&format!("/proc/self/fd/{}", f.as_raw_fd()))));
+    let path = try!(cstr(path));
     match unsafe {
-        libc::open(try!(cstr(dir)).as_ptr(), O_CLOEXEC | O_EXCL | O_TMPFILE | O_RDWR, 0o600)
+        linkat(AT_FDCWD, proc_path.as_ptr(), AT_FDCWD, path.as_ptr(), AT_SYMLINK_FOLLOW)
     } {
-        -1 => create_unix(dir),
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+pub fn reserve(f: &File, size: u64) -> io::Result<()> {
+    // `off64_t` is signed; don't let a huge size wrap around into a negative length.
+    if size > i64::max_value() as u64 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "size too large to reserve"));
+    }
+    // Mode 0 allocates the blocks and extends the file size if necessary. Use the 64-bit variant
+    // so sizes above 2GiB aren't truncated on 32-bit targets.
+    match unsafe { fallocate64(f.as_raw_fd(), 0, 0, size as libc::off64_t) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+pub fn create(dir: &Path, builder: &Builder) -> io::Result<File> {
+    let opts = &builder.opts;
+    if !opts.o_tmpfile {
+        return create_unix(dir, builder);
+    }
+    // Without O_EXCL, the file can later be given a name with linkat (see `link_at`).
+    let excl = if opts.linkable { 0 } else { O_EXCL };
+    match unsafe {
+        libc::open(try!(cstr(dir)).as_ptr(), flags(opts) | excl | O_TMPFILE | O_RDWR,
+                   opts.mode as libc::mode_t)
+    } {
+        -1 => {
+            let err = io::Error::last_os_error();
+            match err.raw_os_error() {
+                // Either the kernel (< 3.11, EISDIR) or the filesystem (EOPNOTSUPP) doesn't
+                // support O_TMPFILE; fall back on creating and then unlinking a file.
+                Some(libc::EOPNOTSUPP) | Some(libc::EISDIR) => create_unix(dir, builder),
+                _ => Err(err),
+            }
+        },
         fd => Ok(unsafe { FromRawFd::from_raw_fd(fd) }),
     }
 }
 
-pub fn create_shared(dir: &Path, count: usize) -> io::Result<Vec<File>> {
+pub fn create_shared_best_effort(dir: &Path, count: usize) -> (Vec<File>, Option<io::Error>) {
     if count == 0 {
-        return Ok(vec![]);
+        return (vec![], None);
+    }
+    let first = match create(dir, &Builder::new()) {
+        Ok(f) => f,
+        Err(e) => return (vec![], Some(e)),
+    };
+    let mut files = Vec::with_capacity(count);
+    let mut error = None;
+    for _ in 1..count {
+        match reopen(&first) {
+            Ok(f) => files.push(f),
+            Err(e) => {
+                error = Some(e);
+                break;
+            },
+        }
     }
-    let first = try!(create(dir));
-    let mut files: Vec<File> = try!((1..count).map(|_| reopen(&first)).collect());
     files.push(first);
-    Ok(files)
+    (files, error)
+}
+
+pub fn into_read_only(f: File) -> io::Result<File> {
+    OpenOptions::new().read(true).open(format!("/dev/fd/{}", f.as_raw_fd()))
 }
 
 pub fn reopen(f: &File) -> io::Result<File> {
@@ -1,3 +1,40 @@
+/// Platform-level creation options, set through `Builder`.
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    /// Permission bits passed to `open` (unix only).
+    pub mode: u32,
+    /// Open the file in append mode.
+    pub append: bool,
+    /// Close the file on exec (unix) or don't let child processes inherit it (windows).
+    pub cloexec: bool,
+    /// Try to create unnamed files with `O_TMPFILE` (linux only).
+    #[cfg(target_os = "linux")]
+    pub o_tmpfile: bool,
+    /// Open the file with `O_DIRECT` (linux only).
+    #[cfg(target_os = "linux")]
+    pub direct: bool,
+    /// Omit `O_EXCL` when creating files with `O_TMPFILE` so they can be linked (linux only).
+    #[cfg(target_os = "linux")]
+    pub linkable: bool,
+}
+
+impl Default for Options {
+    #[inline]
+    fn default() -> Options {
+        Options {
+            mode: 0o600,
+            append: false,
+            cloexec: true,
+            #[cfg(target_os = "linux")]
+            o_tmpfile: true,
+            #[cfg(target_os = "linux")]
+            direct: false,
+            #[cfg(target_os = "linux")]
+            linkable: false,
+        }
+    }
+}
+
 #[cfg(unix)]
 mod unix_common;
 
@@ -1,12 +1,14 @@
 use ::libc::{self, O_EXCL, O_RDWR, O_CREAT};
 use ::libc::types::os::arch::posix01::stat as stat_t;
-use std::io;
+use std::io::{self, Seek, SeekFrom};
 use std::os::unix::io::{RawFd, FromRawFd, AsRawFd};
 use std::fs::{self, File, OpenOptions};
 use std::path::Path;
-use ::util::{tmpname, cstr};
+use ::util::{tmpname, cstr, exchange_by_rename, DEFAULT_PREFIX, NUM_RAND_CHARS};
 use super::unix_common::O_CLOEXEC;
-pub use super::unix_common::{create, create_named};
+use ::Builder;
+pub use super::unix_common::{create, create_named, create_with_options, persist_noclobber,
+                             crosses_devices, sync_dir, same_file, same_file_at};
 
 unsafe fn stat(fd: RawFd) -> io::Result<stat_t> {
     let mut meta: stat_t = ::std::mem::zeroed();
@@ -26,50 +28,86 @@ impl<'a> Drop for DeleteGuard<'a> {
     }
 }
 
-pub fn create_shared(dir: &Path, count: usize) -> io::Result<Vec<File>> {
+pub fn create_shared_best_effort(dir: &Path, count: usize) -> (Vec<File>, Option<io::Error>) {
     let mut opts = OpenOptions::new();
     opts.read(true).write(true).create(false);
 
     if count == 0 {
-        return Ok(vec![]);
+        return (vec![], None);
     }
     'outer: loop {
-        let tmp_path = dir.join(&tmpname());
-        return match unsafe {
-            libc::open(try!(cstr(&tmp_path)).as_ptr(), O_CLOEXEC | O_EXCL | O_RDWR | O_CREAT, 0o600)
-        } {
-            -1 => {
-                let err = io::Error::last_os_error();
-                if err.kind() == io::ErrorKind::AlreadyExists {
-                    continue;
-                } else {
-                    Err(err)
-                }
-            },
-            fd => unsafe {
-                let first = FromRawFd::from_raw_fd(fd);
-                let _dg = DeleteGuard(&tmp_path);
-
-                let target_meta = try!(stat(fd));
-                let mut files: Vec<File> = try!((1..count).map(|_| opts.open(&tmp_path)).collect());
-                for file in &files {
-                    let meta = try!(stat(file.as_raw_fd()));
-                    if meta.st_dev != target_meta.st_dev ||
-                       meta.st_ino != target_meta.st_ino ||
-                       // Even if the device information get's reused, the owner should actually be
-                       // sufficient.
-                       meta.st_uid != target_meta.st_uid ||
-                       meta.st_gid != target_meta.st_gid {
+        let tmp_path = dir.join(&tmpname(DEFAULT_PREFIX, "", NUM_RAND_CHARS));
+        let c_path = match cstr(&tmp_path) {
+            Ok(p) => p,
+            Err(e) => return (vec![], Some(e)),
+        };
+        let fd = unsafe {
+            libc::open(c_path.as_ptr(), O_CLOEXEC | O_EXCL | O_RDWR | O_CREAT, 0o600)
+        };
+        if fd == -1 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::AlreadyExists {
+                continue;
+            }
+            return (vec![], Some(err));
+        }
+        let first: File = unsafe { FromRawFd::from_raw_fd(fd) };
+        let _dg = DeleteGuard(&tmp_path);
 
-                        // Error? Panic? If we hit this, we're likely under attack (or a hardware
-                        // glitch/reconfiguration?.
-                        continue 'outer;
-                    }
+        let target_meta = match unsafe { stat(fd) } {
+            Ok(meta) => meta,
+            Err(e) => return (vec![], Some(e)),
+        };
+        let mut files = Vec::with_capacity(count);
+        let mut error = None;
+        for _ in 1..count {
+            // Stop at the first failure but keep the references opened so far.
+            let file = match opts.open(&tmp_path) {
+                Ok(file) => file,
+                Err(e) => {
+                    error = Some(e);
+                    break;
+                },
+            };
+            let meta = match unsafe { stat(file.as_raw_fd()) } {
+                Ok(meta) => meta,
+                Err(e) => {
+                    error = Some(e);
+                    break;
+                },
+            };
+            if meta.st_dev != target_meta.st_dev ||
+               meta.st_ino != target_meta.st_ino ||
+               // Even if the device information get's reused, the owner should actually be
+               // sufficient.
+               meta.st_uid != target_meta.st_uid ||
+               meta.st_gid != target_meta.st_gid {
 
-                }
-                files.push(first);
-                Ok(files)
-            },
+                // Error? Panic? If we hit this, we're likely under attack (or a hardware
+                // glitch/reconfiguration?.
+                continue 'outer;
+            }
+            files.push(file);
         }
+        files.push(first);
+        return (files, error);
     }
 }
+
+pub fn create_anonymous(dir: &Path) -> io::Result<File> {
+    // macOS has shm_open but its descriptors only support ftruncate and mmap, not read and write,
+    // so they can't back a TempFile. Create and immediately unlink a regular file instead.
+    create(dir, &Builder::new())
+}
+
+pub fn exchange_paths(a: &Path, b: &Path) -> io::Result<()> {
+    // No RENAME_EXCHANGE (macOS has renamex_np with RENAME_SWAP but the BSDs don't).
+    exchange_by_rename(a, b)
+}
+
+pub fn into_read_only(mut f: File) -> io::Result<File> {
+    // The file has already been unlinked so we can't open it again with a read-only access mode;
+    // just hand back the original descriptor.
+    try!(f.seek(SeekFrom::Start(0)));
+    Ok(f)
+}
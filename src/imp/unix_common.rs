@@ -1,33 +1,100 @@
-use ::libc::{self, c_int, O_EXCL, O_RDWR, O_CREAT};
+use ::libc::{self, c_int, O_EXCL, O_RDWR, O_CREAT, O_APPEND};
 use std::io;
 use std::os::unix::io::FromRawFd;
-use std::fs::{self, File};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
+use std::fs::{self, File, OpenOptions};
 use std::path::Path;
-use ::util::{tmpname, cstr};
+use ::util::{create_helper, cstr};
+use ::Builder;
+use super::Options;
 
 pub const O_CLOEXEC: libc::c_int = 0o2000000;
 
-pub fn create(dir: &Path) -> io::Result<File> {
-    loop {
-        let tmp_path = dir.join(&tmpname());
-        return match create_named(&tmp_path) {
-            Ok(file) => {
-                // I should probably tell the user this failed but the temporary file creation
-                // didn't really fail...
-                let _ = fs::remove_file(tmp_path);
-                Ok(file)
-            },
-            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
-            Err(e) => Err(e),
+/// The `open` flags implied by `opts` (on top of the access mode and creation flags).
+pub fn flags(opts: &Options) -> c_int {
+    let mut flags = 0;
+    if opts.cloexec {
+        flags |= O_CLOEXEC;
+    }
+    if opts.append {
+        flags |= O_APPEND;
+    }
+    #[cfg(target_os = "linux")]
+    {
+        if opts.direct {
+            flags |= libc::O_DIRECT;
         }
     }
+    // Without it, files opened by a raw `open` on 32-bit linux are limited to 2GiB (it's implied
+    // on 64-bit targets).
+    #[cfg(all(target_os = "linux", target_pointer_width = "32"))]
+    {
+        flags |= libc::O_LARGEFILE;
+    }
+    flags
+}
+
+/// Create a file in `dir` and immediately unlink it, retrying according to `builder`.
+pub fn create(dir: &Path, builder: &Builder) -> io::Result<File> {
+    create_helper(dir, builder, |tmp_path| {
+        let file = try!(create_named(&tmp_path, &builder.opts));
+        // I should probably tell the user this failed but the temporary file creation
+        // didn't really fail...
+        let _ = fs::remove_file(tmp_path);
+        Ok(file)
+    })
 }
 
-pub fn create_named(path: &Path) -> io::Result<File> {
+pub fn create_with_options(dir: &Path, open_opts: &OpenOptions) -> io::Result<File> {
+    let mut open_opts = open_opts.clone();
+    open_opts.create_new(true).mode(0o600);
+    create_helper(dir, &Builder::new(), |tmp_path| {
+        let file = try!(open_opts.open(&tmp_path));
+        let _ = fs::remove_file(tmp_path);
+        Ok(file)
+    })
+}
+
+pub fn create_named(path: &Path, opts: &Options) -> io::Result<File> {
     return match unsafe {
-        libc::open(try!(cstr(&path)).as_ptr(), O_CLOEXEC | O_EXCL | O_RDWR | O_CREAT, 0o600)
+        libc::open(try!(cstr(&path)).as_ptr(), flags(opts) | O_EXCL | O_RDWR | O_CREAT,
+                   opts.mode as libc::mode_t)
     } {
         -1 => Err(io::Error::last_os_error()),
         fd => Ok(unsafe { FromRawFd::from_raw_fd(fd) }),
     }
 }
+
+pub fn same_file(a: &File, b: &File) -> io::Result<bool> {
+    let a_meta = try!(a.metadata());
+    let b_meta = try!(b.metadata());
+    Ok(a_meta.dev() == b_meta.dev() && a_meta.ino() == b_meta.ino())
+}
+
+pub fn same_file_at(file: &File, path: &Path) -> io::Result<bool> {
+    let path_meta = try!(fs::metadata(path));
+    let file_meta = try!(file.metadata());
+    Ok(path_meta.dev() == file_meta.dev() && path_meta.ino() == file_meta.ino())
+}
+
+pub fn sync_dir(dir: &Path) -> io::Result<()> {
+    try!(File::open(dir)).sync_all()
+}
+
+pub fn crosses_devices(err: &io::Error) -> bool {
+    err.raw_os_error() == Some(libc::EXDEV)
+}
+
+pub fn persist_noclobber(old_path: &Path, new_path: &Path) -> io::Result<()> {
+    unsafe {
+        let old_path = try!(cstr(old_path));
+        let new_path = try!(cstr(new_path));
+        // `link` fails with EEXIST instead of replacing the target.
+        if libc::link(old_path.as_ptr(), new_path.as_ptr()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // The file has been persisted; failing to remove the old name just leaks a link.
+        libc::unlink(old_path.as_ptr());
+    }
+    Ok(())
+}
@@ -1,10 +1,18 @@
 use std::os::windows::fs::OpenOptionsExt;
 use std::os::windows::io::{FromRawHandle, AsRawHandle, RawHandle};
+use std::os::windows::ffi::OsStrExt;
+use std::ffi::OsStr;
 use std::path::Path;
 use std::io;
 use std::fs::{File, OpenOptions};
-use ::libc::{self, DWORD, HANDLE};
-use ::util::tmpname;
+use ::libc::{self, DWORD, HANDLE, BOOL};
+use ::util::{create_helper, exchange_by_rename};
+use ::Builder;
+use super::Options;
+
+const ERROR_NOT_SAME_DEVICE: i32 = 17;
+const FILE_WRITE_DATA: DWORD = 0x0002;
+const HANDLE_FLAG_INHERIT: DWORD = 0x0001;
 
 const ACCESS: DWORD     = libc::FILE_GENERIC_READ
                         | libc::FILE_GENERIC_WRITE;
@@ -23,39 +31,132 @@ extern "system" {
                   dwDesiredAccess: DWORD,
                   dwShareMode: DWORD,
                   dwFlags: DWORD) -> HANDLE;
+    fn MoveFileExW(lpExistingFileName: *const u16,
+                   lpNewFileName: *const u16,
+                   dwFlags: DWORD) -> BOOL;
+    fn SetHandleInformation(hObject: HANDLE, dwMask: DWORD, dwFlags: DWORD) -> BOOL;
+    fn GetFileInformationByHandle(hFile: HANDLE,
+                                  lpFileInformation: *mut ByHandleFileInformation) -> BOOL;
+}
+
+#[repr(C)]
+struct ByHandleFileInformation {
+    file_attributes: DWORD,
+    creation_time: [DWORD; 2],
+    last_access_time: [DWORD; 2],
+    last_write_time: [DWORD; 2],
+    volume_serial_number: DWORD,
+    file_size_high: DWORD,
+    file_size_low: DWORD,
+    number_of_links: DWORD,
+    file_index_high: DWORD,
+    file_index_low: DWORD,
+}
+
+// Returns the volume serial number and file index that, together, uniquely identify a file.
+fn file_id(f: &File) -> io::Result<(DWORD, DWORD, DWORD)> {
+    unsafe {
+        let mut info: ByHandleFileInformation = ::std::mem::zeroed();
+        if GetFileInformationByHandle(f.as_raw_handle() as HANDLE, &mut info) == 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok((info.volume_serial_number, info.file_index_high, info.file_index_low))
+        }
+    }
+}
+
+fn to_utf16(s: &OsStr) -> Vec<u16> {
+    s.encode_wide().chain(Some(0)).collect()
+}
+
+fn access(opts: &Options) -> DWORD {
+    if opts.append {
+        // Keep FILE_APPEND_DATA but drop FILE_WRITE_DATA so all writes go to the end.
+        ACCESS & !FILE_WRITE_DATA
+    } else {
+        ACCESS
+    }
 }
 
 
-pub fn create_named(path: &Path) -> io::Result<File> {
-    OpenOptions::new().desired_access(ACCESS as i32)
+// Files are opened non-inheritable; make them inheritable if requested.
+fn set_inheritable(f: File, opts: &Options) -> io::Result<File> {
+    if opts.cloexec {
+        return Ok(f);
+    }
+    if unsafe { SetHandleInformation(f.as_raw_handle() as HANDLE, HANDLE_FLAG_INHERIT,
+                                     HANDLE_FLAG_INHERIT) } == 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(f)
+    }
+}
+
+pub fn create_named(path: &Path, opts: &Options) -> io::Result<File> {
+    OpenOptions::new().desired_access(access(opts) as i32)
         .share_mode(SHARE_MODE as i32)
         .creation_disposition(libc::CREATE_NEW as i32)
         .flags_and_attributes(FLAGS as i32).open(path)
+        .and_then(|f| set_inheritable(f, opts))
 }
 
-pub fn create(dir: &Path) -> io::Result<File> {
-    let mut opts = OpenOptions::new();
-    opts.desired_access(ACCESS as i32)
+pub fn create(dir: &Path, builder: &Builder) -> io::Result<File> {
+    let opts = &builder.opts;
+    let mut open_opts = OpenOptions::new();
+    open_opts.desired_access(access(opts) as i32)
         .share_mode(SHARE_MODE as i32)
         .creation_disposition(libc::CREATE_NEW as i32)
         .flags_and_attributes(FLAGS_DEL as i32);
-    loop {
-        return match opts.open(&dir.join(&tmpname())) {
-            Ok(f) => Ok(f),
-            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
-            Err(e) => Err(e),
-        };
-    }
+    create_helper(dir, builder, |path| open_opts.open(&path))
+        .and_then(|f| set_inheritable(f, opts))
+}
+
+pub fn create_with_options(dir: &Path, open_opts: &OpenOptions) -> io::Result<File> {
+    let mut open_opts = open_opts.clone();
+    open_opts.create_new(true)
+        .share_mode(SHARE_MODE as i32)
+        .flags_and_attributes(FLAGS_DEL as i32);
+    create_helper(dir, &Builder::new(), |path| open_opts.open(&path))
+}
+
+pub fn create_anonymous(dir: &Path) -> io::Result<File> {
+    // Files can't be unlinked while open on windows; DeleteOnClose is as close as it gets.
+    create(dir, &Builder::new())
 }
 
-pub fn create_shared(dir: &Path, count: usize) -> io::Result<Vec<File>> {
+pub fn create_shared_best_effort(dir: &Path, count: usize) -> (Vec<File>, Option<io::Error>) {
     if count == 0 {
-        return Ok(vec![]);
+        return (vec![], None);
+    }
+    let first = match create(dir, &Builder::new()) {
+        Ok(f) => f,
+        Err(e) => return (vec![], Some(e)),
+    };
+    let mut files = Vec::with_capacity(count);
+    let mut error = None;
+    for _ in 1..count {
+        match reopen(&first) {
+            Ok(f) => files.push(f),
+            Err(e) => {
+                error = Some(e);
+                break;
+            },
+        }
     }
-    let first = try!(create(dir));
-    let mut files: Vec<File> = try!((1..count).map(|_| reopen(&first)).collect());
     files.push(first);
-    Ok(files)
+    (files, error)
+}
+
+pub fn into_read_only(f: File) -> io::Result<File> {
+    let h = f.as_raw_handle();
+    unsafe {
+        let h = ReOpenFile(h as HANDLE, libc::FILE_GENERIC_READ, SHARE_MODE, 0);
+        if h == libc::INVALID_HANDLE_VALUE {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(FromRawHandle::from_raw_handle(h as RawHandle))
+        }
+    }
 }
 
 pub fn reopen(f: &File) -> io::Result<File> {
@@ -69,3 +170,37 @@ pub fn reopen(f: &File) -> io::Result<File> {
         }
     }
 }
+
+pub fn crosses_devices(err: &io::Error) -> bool {
+    err.raw_os_error() == Some(ERROR_NOT_SAME_DEVICE)
+}
+
+pub fn same_file(a: &File, b: &File) -> io::Result<bool> {
+    Ok(try!(file_id(a)) == try!(file_id(b)))
+}
+
+pub fn same_file_at(file: &File, path: &Path) -> io::Result<bool> {
+    let other = try!(OpenOptions::new().read(true).open(path));
+    same_file(file, &other)
+}
+
+pub fn sync_dir(_dir: &Path) -> io::Result<()> {
+    // Directories can't be flushed on windows; NTFS journals metadata (including renames) itself.
+    Ok(())
+}
+
+pub fn exchange_paths(a: &Path, b: &Path) -> io::Result<()> {
+    // There is no atomic exchange on windows.
+    exchange_by_rename(a, b)
+}
+
+pub fn persist_noclobber(old_path: &Path, new_path: &Path) -> io::Result<()> {
+    let old_path = to_utf16(old_path.as_os_str());
+    let new_path = to_utf16(new_path.as_os_str());
+    // Without MOVEFILE_REPLACE_EXISTING, this fails if the target exists.
+    if unsafe { MoveFileExW(old_path.as_ptr(), new_path.as_ptr(), 0) } == 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
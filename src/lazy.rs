@@ -0,0 +1,72 @@
+use std::io::{self, Read, Write, Seek, SeekFrom};
+
+use TempFile;
+
+/// An unnamed temporary file that is only created on first use.
+///
+/// Creating a temporary file can fail so neither `TempFile` nor `NamedTempFile` implement
+/// `Default` (a `Default` implementation would have to panic). `LazyTempFile` does: it only
+/// creates the underlying `TempFile` (in the default temporary directory) the first time it is
+/// read from, written to, or seeked, and any creation error is reported by that operation. This
+/// makes it usable in `#[derive(Default)]` structs and other `Default`-requiring generic code.
+#[derive(Debug, Default)]
+pub struct LazyTempFile {
+    file: Option<TempFile>,
+}
+
+impl LazyTempFile {
+    /// Create a new lazy temporary file. This doesn't touch the filesystem.
+    #[inline]
+    pub fn new() -> LazyTempFile {
+        LazyTempFile { file: None }
+    }
+
+    /// Returns true if the underlying temporary file has been created.
+    #[inline]
+    pub fn is_created(&self) -> bool {
+        self.file.is_some()
+    }
+
+    /// Get the underlying temporary file, creating it if necessary.
+    pub fn get(&mut self) -> io::Result<&mut TempFile> {
+        if self.file.is_none() {
+            self.file = Some(try!(TempFile::new()));
+        }
+        Ok(self.file.as_mut().unwrap())
+    }
+
+    /// Extract the underlying temporary file, creating it if necessary.
+    #[inline]
+    pub fn into_inner(mut self) -> io::Result<TempFile> {
+        try!(self.get());
+        Ok(self.file.take().unwrap())
+    }
+}
+
+impl Read for LazyTempFile {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        try!(self.get()).read(buf)
+    }
+}
+
+impl Write for LazyTempFile {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        try!(self.get()).write(buf)
+    }
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        match self.file {
+            Some(ref mut file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Seek for LazyTempFile {
+    #[inline]
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        try!(self.get()).seek(pos)
+    }
+}
@@ -7,6 +7,15 @@
 //! between the variants, prefer `TempFile` unless you either need to know the file's path or to be
 //! able to persist it.
 //!
+//! For scratch space that needs more than a single file, `TempDir` creates a temporary directory
+//! that is recursively deleted when dropped. `SpooledTempFile` keeps small temporary files in
+//! memory and only rolls over to a `TempFile` once they grow past a size threshold.
+//! `SecureTempFile` overwrites its contents with zeros before releasing the file. `LazyTempFile`
+//! implements `Default` by deferring the creation of a `TempFile` until it's first used.
+//!
+//! With the `tokio` feature, the `tokio` module provides `AsyncTempFile` for use in asynchronous
+//! code.
+//!
 //! # Differences
 //!
 //! ## Resource Leaking
@@ -25,16 +34,226 @@
 //!
 extern crate libc;
 extern crate rand;
+#[cfg(feature = "tokio")]
+extern crate tokio as tokio_crate;
+#[cfg(feature = "memmap2")]
+extern crate memmap2;
 
-use std::io::{self, Read, Write, Seek, SeekFrom};
-use std::fs::{self, File};
+use std::io::{self, Read, Write, Seek, SeekFrom, IoSlice, IoSliceMut, BufReader};
+use std::fs::{self, File, OpenOptions};
 use std::path::{Path, PathBuf};
 use std::error;
 use std::fmt;
 use std::env;
+use std::ffi::{OsStr, OsString};
+use std::convert::TryFrom;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::AtomicU64;
+use std::time::Duration;
+use std::hash::{Hash, Hasher};
+use std::panic::{self, AssertUnwindSafe};
+
+/// Create a `TempFile`, panicking on failure.
+///
+/// `tempfile!()` expands to `TempFile::new().unwrap()` and `tempfile!(in dir)` expands to
+/// `TempFile::new_in(dir).unwrap()`.
+///
+/// ```
+/// #[macro_use] extern crate tempfile;
+/// use std::io::{Write, Read, Seek, SeekFrom};
+///
+/// # fn main() {
+/// let mut tmpfile = tempfile!();
+/// write!(tmpfile, "abcde").unwrap();
+/// tmpfile.seek(SeekFrom::Start(0)).unwrap();
+///
+/// let mut buf = String::new();
+/// tmpfile.read_to_string(&mut buf).unwrap();
+/// assert_eq!("abcde", buf);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! tempfile {
+    () => ($crate::TempFile::new().unwrap());
+    (in $dir:expr) => ($crate::TempFile::new_in($dir).unwrap());
+}
 
 mod imp;
 mod util;
+mod dir;
+mod spooled;
+mod secure;
+mod lazy;
+mod temp_path;
+#[cfg(target_os = "linux")]
+mod seal;
+#[cfg(feature = "tokio")]
+pub mod tokio;
+
+pub use dir::TempDir;
+pub use spooled::SpooledTempFile;
+pub use secure::SecureTempFile;
+pub use lazy::LazyTempFile;
+pub use temp_path::TempPath;
+#[cfg(target_os = "linux")]
+pub use seal::SealFlags;
+
+static DEFAULT_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// The directory in which temporary files are created when no directory is specified.
+///
+/// This is the directory set with `set_default_dir` if any. Otherwise, it's
+/// `std::env::temp_dir()`, which resolves to:
+///
+/// * *nix: the `TMPDIR` environment variable if set, otherwise `/tmp` (`/data/local/tmp` on
+///   Android).
+/// * Windows: the first of the `TMP`, `TEMP`, and `USERPROFILE` environment variables that is set,
+///   otherwise the Windows directory (this is what `GetTempPath` does).
+///
+/// The directory isn't checked for existence or writability.
+pub fn default_temp_dir() -> PathBuf {
+    match *DEFAULT_DIR.read().unwrap_or_else(|e| e.into_inner()) {
+        Some(ref dir) => dir.clone(),
+        None => env::temp_dir(),
+    }
+}
+
+/// Override the default temporary directory for the whole process.
+///
+/// All functions that don't take a directory (`TempFile::new`, `NamedTempFile::new`,
+/// `TempDir::new`, etc.) will create their files in `dir` until `clear_default_dir` is called.
+/// This is thread-safe but, as it affects every thread, it's best called once, early on.
+pub fn set_default_dir<P: AsRef<Path>>(dir: P) {
+    *DEFAULT_DIR.write().unwrap_or_else(|e| e.into_inner()) = Some(dir.as_ref().to_path_buf());
+}
+
+/// Remove the override set by `set_default_dir`, reverting to `std::env::temp_dir()`.
+pub fn clear_default_dir() {
+    *DEFAULT_DIR.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+type CleanupErrorHook = Box<Fn(&Path, &io::Error) + Send + Sync>;
+
+static CLEANUP_ERROR_HOOK: RwLock<Option<CleanupErrorHook>> = RwLock::new(None);
+
+/// Set a function to be called whenever a destructor fails to delete a temporary file or
+/// directory.
+///
+/// Destructors (of `NamedTempFile`, `TempPath`, and `TempDir`) can't return errors so, by default,
+/// failures to clean up are silently ignored. The hook receives the path that couldn't be deleted
+/// and the error; use it to log or count leaked temporary files. Panics in the hook are caught and
+/// ignored. The hook must not set or clear the hook itself.
+///
+/// This only affects destructors; `close` still returns its error.
+pub fn set_cleanup_error_hook<F>(hook: F)
+    where F: Fn(&Path, &io::Error) + Send + Sync + 'static
+{
+    *CLEANUP_ERROR_HOOK.write().unwrap_or_else(|e| e.into_inner()) = Some(Box::new(hook));
+}
+
+/// Remove the hook set by `set_cleanup_error_hook`.
+pub fn clear_cleanup_error_hook() {
+    *CLEANUP_ERROR_HOOK.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+// Report a cleanup failure in a destructor to the hook, if any.
+fn cleanup_failed(path: &Path, err: &io::Error) {
+    if let Some(ref hook) = *CLEANUP_ERROR_HOOK.read().unwrap_or_else(|e| e.into_inner()) {
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| hook(path, err)));
+    }
+}
+
+static TRACKED: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+fn track(path: &Path) {
+    TRACKED.lock().unwrap_or_else(|e| e.into_inner()).push(path.to_path_buf());
+}
+
+fn untrack(path: &Path) {
+    let mut tracked = TRACKED.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(i) = tracked.iter().rposition(|p| p == path) {
+        tracked.swap_remove(i);
+    }
+}
+
+/// Delete all files created with `NamedTempFile::new_tracked` that are still alive.
+///
+/// This is a last resort for when destructors won't run, e.g. from a panic hook in a binary built
+/// with `panic = "abort"` (the hook runs before the process aborts) or before calling
+/// `std::process::exit`. It's best-effort: errors are ignored, files created through other means
+/// aren't covered, and nothing helps if the process is killed. As it takes a lock, it must not be
+/// called from a signal handler. The `NamedTempFile`s whose files were deleted remain usable
+/// (their destructors will just fail to delete them again).
+pub fn cleanup_tracked() {
+    let mut tracked = TRACKED.lock().unwrap_or_else(|e| e.into_inner());
+    for path in tracked.drain(..) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// List the files in `dir` that look like temporary files left behind by this crate.
+///
+/// A file matches if it is a regular file (not a directory or symlink) whose name is exactly
+/// `prefix` followed by six ASCII letters or digits, i.e. the names generated with the default
+/// `Builder` settings apart from the prefix (the default prefix is `"."`). Files created with a
+/// suffix, a different number of random characters, a custom alphabet, or a counter don't match.
+///
+/// Nothing guarantees that a matching file was actually created by this crate, or that it isn't
+/// still in use, so pick a distinctive prefix and check e.g. the file's age before deleting it.
+/// The paths are returned sorted.
+pub fn find_leftovers<P: AsRef<Path>>(dir: P, prefix: &str) -> io::Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    for entry in try!(fs::read_dir(dir)) {
+        let entry = try!(entry);
+        let matches = match entry.file_name().to_str() {
+            Some(name) if name.starts_with(prefix) => {
+                let rand = &name[prefix.len()..];
+                rand.len() == util::NUM_RAND_CHARS &&
+                    rand.bytes().all(|b| b.is_ascii_alphanumeric())
+            },
+            _ => false,
+        };
+        if matches && try!(fs::symlink_metadata(entry.path())).is_file() {
+            found.push(entry.path());
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+/// Swap the files (or directories) at `a` and `b`.
+///
+/// On Linux, this uses `renameat2` with `RENAME_EXCHANGE`, which is atomic: at any point in time,
+/// both paths exist and refer to either the old or the new files. Both paths must be on the same
+/// filesystem.
+///
+/// Elsewhere, and on Linux if the kernel or filesystem doesn't support `RENAME_EXCHANGE`, this
+/// silently falls back on three renames through a temporary name in `a`'s directory. The fallback
+/// is **not** atomic: there is a window during which `a` doesn't exist, and a crash in the middle
+/// can leave `a`'s file under the temporary name. On failure, the fallback tries to restore the
+/// original state. Callers that need atomicity on Linux should make sure the filesystem supports
+/// `RENAME_EXCHANGE` (ext4, btrfs, xfs and tmpfs do).
+#[inline]
+pub fn exchange_paths<P: AsRef<Path>, Q: AsRef<Path>>(a: P, b: Q) -> io::Result<()> {
+    imp::exchange_paths(a.as_ref(), b.as_ref())
+}
+
+/// Atomically create or replace `final_path` with contents written by `f`.
+///
+/// This creates a `NamedTempFile` in `dir` (which should be on the same filesystem as
+/// `final_path`, usually its parent), lets `f` populate it, flushes and syncs it to disk, and then
+/// persists it to `final_path`. If any step fails, including `f`, the temporary file is deleted and
+/// `final_path` is left untouched.
+pub fn persist_with<P, Q, F>(dir: P, final_path: Q, f: F) -> io::Result<File>
+    where P: AsRef<Path>, Q: AsRef<Path>, F: FnOnce(&mut NamedTempFile) -> io::Result<()>
+{
+    let mut tmpfile = try!(NamedTempFile::new_in(dir));
+    try!(f(&mut tmpfile));
+    try!(tmpfile.flush());
+    try!(tmpfile.sync_all());
+    Ok(try!(tmpfile.persist(final_path)))
+}
 
 /// An unnamed temporary file.
 ///
@@ -50,272 +269,1718 @@ mod util;
 /// Windows: The temporary file is marked DeleteOnClose and, again, will be deleted when the last
 /// open copy of it is closed. Unlike *nix operating systems, the file is not immediately unlinked
 /// from the filesystem.
-pub struct TempFile(File);
+pub struct TempFile {
+    file: File,
+    dir: Option<PathBuf>,
+    // Only set if deletion was deferred with `Builder::unlink_now(false)` or
+    // `Builder::delete_on_close(false)`. Declared after `file` so the file is closed first.
+    path: Option<TempPath>,
+}
 
 impl TempFile {
     /// Create a new temporary file.
+    ///
+    /// On failure, the returned `CreateError` holds the directory in which the file was to be
+    /// created (it converts into an `io::Error` for use with `try!`).
     #[inline]
-    pub fn new() -> io::Result<TempFile> {
-        Self::new_in(&env::temp_dir())
+    pub fn new() -> Result<TempFile, CreateError> {
+        Self::new_in(&default_temp_dir())
     }
 
     /// Create a new temporary file in the specified directory.
-    #[inline]
-    pub fn new_in<P: AsRef<Path>>(dir: P) -> io::Result<TempFile> {
-        imp::create(dir.as_ref()).map(|f| TempFile(f))
-    }
-
-    /// Create a new temporary file and open it `count` times returning `count` independent
-    /// references to the same file.
     ///
-    /// This can be useful if you want multiple seek positions in the same temporary file.
-    /// Additionally, this function guarantees that all of the returned temporary file objects
-    /// refer to the same underlying temporary file even in the presence of a pathological
-    /// temporary file cleaner.
+    /// See `new` regarding errors.
     #[inline]
-    pub fn shared(count: usize) -> io::Result<Vec<TempFile>> {
-        Self::shared_in(&env::temp_dir(), count)
+    pub fn new_in<P: AsRef<Path>>(dir: P) -> Result<TempFile, CreateError> {
+        let dir = dir.as_ref();
+        Builder::new().unnamed_in(dir).map_err(|e| {
+            CreateError { error: e, dir: dir.to_path_buf() }
+        })
     }
 
-    /// Same as `shared` but creates the file in the specified directory.
+    /// Create a new temporary file holding `contents`, with the offset at the start.
     #[inline]
-    pub fn shared_in<P: AsRef<Path>>(dir: P, count: usize) -> io::Result<Vec<TempFile>> {
-        imp::create_shared(dir.as_ref(), count).map(|files| {
-            files.into_iter().map(|f|TempFile(f)).collect()
-        })
+    pub fn with_contents(contents: &[u8]) -> io::Result<TempFile> {
+        Self::with_contents_in(&default_temp_dir(), contents)
     }
 
+    /// Same as `with_contents` but creates the file in the specified directory.
+    pub fn with_contents_in<P: AsRef<Path>>(dir: P, contents: &[u8]) -> io::Result<TempFile> {
+        let mut tmpfile = try!(Self::new_in(dir));
+        try!(tmpfile.write_all(contents));
+        try!(tmpfile.rewind_for_read());
+        Ok(tmpfile)
+    }
 
-    /// Number of bytes in the file.
+    /// Returns an iterator that lazily creates a new, distinct temporary file each time it's
+    /// advanced.
+    ///
+    /// See `iter_in`.
     #[inline]
-    pub fn len(&self) -> io::Result<u64> {
-        self.0.metadata().map(|m| m.len())
+    pub fn iter() -> TempFileIter {
+        Self::iter_in(default_temp_dir())
     }
 
-    /// Truncate the file to `size` bytes.
-    #[inline(always)]
-    pub fn set_len(&self, size: u64) -> io::Result<()> {
-        self.0.set_len(size)
+    /// Returns an iterator that lazily creates a new, distinct temporary file in the specified
+    /// directory each time it's advanced.
+    ///
+    /// Unlike `shared`, every item is a different file. The iterator is infinite until creating a
+    /// file fails: it then yields the error and ends.
+    #[inline]
+    pub fn iter_in<P: AsRef<Path>>(dir: P) -> TempFileIter {
+        TempFileIter { dir: dir.as_ref().to_path_buf(), done: false }
     }
 
-    /// Re-open the temporary file. The returned TempFile will refer to the same underlying
-    /// temporary file but will have an independent offset.
-    ///
-    /// This method is only available on windows and Linux, not FreeBSD/MacOS. Unfortunately, it is
-    /// impossible to reliably implement this method on those operating systems.
-    ///
-    /// If you need your code to be cross-platform, please use `shared`/`shared_in` defined above.
+    /// Create a new temporary file extended to `size` bytes.
     ///
-    /// **Unstable**: This is platform specific and may go away in the future.
-    #[cfg(any(windows, target_os = "linux"))]
+    /// The file is extended with `set_len` so, on filesystems that support it, it is *sparse*: no
+    /// disk space is actually allocated until data is written and reading the unwritten parts
+    /// yields zeros. Writes may therefore still fail with "no space left on device" later on. Use
+    /// `new_sized_reserved` (Linux) to allocate the space up front.
     #[inline]
-    pub fn reopen(&self) -> io::Result<TempFile> {
-        imp::reopen(&self.0).map(|f|TempFile(f))
+    pub fn new_sized(size: u64) -> io::Result<TempFile> {
+        Self::new_sized_in(&default_temp_dir(), size)
     }
-}
 
-impl Read for TempFile {
-    #[inline(always)]
-    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        self.0.read(buf)
+    /// Same as `new_sized` but creates the file in the specified directory.
+    pub fn new_sized_in<P: AsRef<Path>>(dir: P, size: u64) -> io::Result<TempFile> {
+        let tmpfile = try!(Self::new_in(dir));
+        try!(tmpfile.set_len(size));
+        Ok(tmpfile)
     }
-}
 
-impl Write for TempFile {
-    #[inline(always)]
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.0.write(buf)
+    /// Create a new temporary file of `size` bytes with all of its disk space allocated up front
+    /// (using `fallocate`).
+    ///
+    /// Unlike `new_sized`, the file isn't sparse and fails immediately if there isn't enough space.
+    #[cfg(target_os = "linux")]
+    #[inline]
+    pub fn new_sized_reserved(size: u64) -> io::Result<TempFile> {
+        Self::new_sized_reserved_in(&default_temp_dir(), size)
     }
-    #[inline(always)]
-    fn flush(&mut self) -> io::Result<()> {
-        self.0.flush()
+
+    /// Same as `new_sized_reserved` but creates the file in the specified directory.
+    #[cfg(target_os = "linux")]
+    pub fn new_sized_reserved_in<P: AsRef<Path>>(dir: P, size: u64) -> io::Result<TempFile> {
+        let tmpfile = try!(Self::new_in(dir));
+        try!(imp::reserve(&tmpfile.file, size));
+        Ok(tmpfile)
     }
-}
 
-impl Seek for TempFile {
-    #[inline(always)]
-    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
-        self.0.seek(pos)
+    /// Same as `new_in` but first checks that `dir` exists and is a directory.
+    ///
+    /// This fails with a descriptive `NotFound` or `NotADirectory` error instead of whatever the
+    /// OS reports when creating the file. The check costs an extra `stat` so `new_in` doesn't do
+    /// it.
+    pub fn new_in_checked<P: AsRef<Path>>(dir: P) -> io::Result<TempFile> {
+        let dir = dir.as_ref();
+        let meta = match fs::metadata(dir) {
+            Ok(meta) => meta,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(io::Error::new(io::ErrorKind::NotFound,
+                                          format!("temporary directory {:?} does not exist", dir)));
+            },
+            Err(e) => return Err(e),
+        };
+        if !meta.is_dir() {
+            return Err(io::Error::new(io::ErrorKind::NotADirectory,
+                                      format!("{:?} is not a directory", dir)));
+        }
+        Ok(try!(Self::new_in(dir)))
     }
-}
 
-#[cfg(unix)]
-impl std::os::unix::io::AsRawFd for TempFile {
-    #[inline(always)]
-    fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
-        self.0.as_raw_fd()
+    /// Create a new temporary file in `/dev/shm` (Linux only), regardless of `TMPDIR` and
+    /// `set_default_dir`.
+    ///
+    /// `/dev/shm` is a `tmpfs`: the file lives in RAM (and possibly swap) and counts against the
+    /// system's memory, not its disk space, which makes it fast scratch space for data that fits
+    /// in memory. Fails with `NotFound` if `/dev/shm` doesn't exist, `NotADirectory` if it isn't
+    /// a directory, and `PermissionDenied` if it isn't writable. Use `new_in_shm_or_default` to fall
+    /// back on the default temporary directory instead.
+    #[cfg(target_os = "linux")]
+    pub fn new_in_shm() -> io::Result<TempFile> {
+        Self::new_in_checked("/dev/shm").map_err(|e| {
+            if e.kind() == io::ErrorKind::PermissionDenied {
+                io::Error::new(io::ErrorKind::PermissionDenied, "/dev/shm is not writable")
+            } else {
+                e
+            }
+        })
     }
-}
 
-#[cfg(windows)]
-impl std::os::windows::io::AsRawHandle for TempFile {
-    #[inline(always)]
-    fn as_raw_handle(&self) -> std::os::windows::io::RawHandle {
-        self.0.as_raw_handle()
+    /// Same as `new_in_shm` but falls back on the default temporary directory if the file can't be
+    /// created in `/dev/shm` (Linux only).
+    #[cfg(target_os = "linux")]
+    #[inline]
+    pub fn new_in_shm_or_default() -> io::Result<TempFile> {
+        Self::new_in_shm().or_else(|_| Self::new().map_err(io::Error::from))
     }
-}
 
-/// A named temporary file.
-///
-/// This variant is *NOT* secure/reliable in the presence of a pathological temporary file cleaner.
-///
-/// NamedTempFiles are deleted on drop. As rust doesn't guarantee that a struct will ever be
-/// dropped, these temporary files will not be deleted on abort, resource leak, early exit, etc.
-///
-/// Please use TempFile unless you absolutely need a named file.
-///
-pub struct NamedTempFile(Option<NamedTempFileInner>);
+    /// Create a new temporary file that, as far as the platform allows, never has a path.
+    ///
+    /// * Linux: an anonymous, RAM-backed file from `memfd_create` (see `new_memfd`). It counts
+    ///   against memory, not disk space.
+    /// * Other *nix (including macOS): a disk-backed file in the default temporary directory,
+    ///   unlinked immediately after being created, so its path is only visible for an instant.
+    ///   (macOS's `shm_open` isn't used as its descriptors don't support `read` and `write`.)
+    /// * Windows: a disk-backed file marked `DeleteOnClose` in the default temporary directory. Its
+    ///   path stays visible until it's closed.
+    ///
+    /// `dir` returns `None` for the memory-backed files.
+    pub fn new_anonymous() -> io::Result<TempFile> {
+        let dir = default_temp_dir();
+        let file = try!(imp::create_anonymous(&dir));
+        let dir = if cfg!(target_os = "linux") { None } else { Some(dir) };
+        Ok(TempFile { file: file, dir: dir, path: None })
+    }
 
-struct NamedTempFileInner {
-    file: File,
-    path: PathBuf,
-}
+    /// Create a new temporary file in `dir`, opened with `opts`.
+    ///
+    /// `opts` is used as a base and the crate adds what it needs to create a fresh, private file
+    /// that is deleted automatically: `create_new`, `0o600` permissions (unix), and a
+    /// delete-on-close share mode and flags (windows). These override the corresponding settings in
+    /// `opts`; everything else (e.g. `append`) is kept.
+    ///
+    /// `opts` must request write or append access; otherwise this fails with `InvalidInput`.
+    pub fn with_options<P: AsRef<Path>>(opts: &OpenOptions, dir: P) -> io::Result<TempFile> {
+        let dir = dir.as_ref();
+        let file = try!(imp::create_with_options(dir, opts));
+        Ok(TempFile { file: file, dir: Some(dir.to_path_buf()), path: None })
+    }
 
-impl fmt::Debug for NamedTempFile {
+    /// Create a new anonymous, memory-backed temporary file with `memfd_create` (Linux >= 3.17
+    /// only).
+    ///
+    /// The file never touches any filesystem path; it lives in memory (like a file on a `tmpfs`)
+    /// and counts against the process's memory usage, possibly being swapped out. It supports
+    /// everything regular temporary files do, including `set_len` and memory mapping, and can be
+    /// sealed with `add_seals`. `name` is only used for debugging (it shows up in `/proc/self/fd`)
+    /// and doesn't need to be unique.
+    #[cfg(target_os = "linux")]
     #[inline]
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "NamedTempFile({:?})", self.0.as_ref().unwrap().path)
+    pub fn new_memfd(name: &str) -> io::Result<TempFile> {
+        imp::create_memfd(name).map(|f| TempFile { file: f, dir: None, path: None })
     }
-}
-
-/// Error returned when persisting a temporary file fails
-#[derive(Debug)]
-pub struct PersistError {
-    /// The underlying IO error.
-    pub error: io::Error,
-    /// The temporary file that couldn't be persisted.
-    pub file: NamedTempFile,
-}
 
-impl From<PersistError> for io::Error {
+    /// Add seals to a memfd-backed temporary file (Linux only).
+    ///
+    /// Once added, seals can't be removed and apply to every handle to the file, including those
+    /// held by other processes. Adding `SealFlags::WRITE` fails with `EBUSY` if the file is
+    /// currently memory-mapped for writing.
+    ///
+    /// Only files created with `new_memfd` support sealing; this fails for other temporary files
+    /// (with `EINVAL`, or `EPERM` on `tmpfs`) and with `EPERM` if the file has been sealed with
+    /// `SealFlags::SEAL`.
+    #[cfg(target_os = "linux")]
     #[inline]
-    fn from(error: PersistError) -> io::Error {
-        error.error
+    pub fn add_seals(&self, seals: SealFlags) -> io::Result<()> {
+        imp::add_seals(&self.file, seals.bits())
     }
-}
 
-impl fmt::Display for PersistError {
+    /// Get the seals applied to a memfd-backed temporary file (Linux only).
+    ///
+    /// Fails with `EINVAL` for files that don't support sealing at all.
+    #[cfg(target_os = "linux")]
     #[inline]
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "failed to persist temporary file: {}", self.error)
+    pub fn get_seals(&self) -> io::Result<SealFlags> {
+        imp::get_seals(&self.file).map(SealFlags::from_bits)
     }
-}
 
-impl error::Error for PersistError {
+    /// Wrap an already-open file.
+    ///
+    /// **Warning**: This does *not* unlink the file. The `TempFile` can't know the file's path so,
+    /// unless the file has already been deleted (or was never linked into the filesystem), it will
+    /// be left behind when the `TempFile` is dropped. Use `NamedTempFile::from_parts` if you need
+    /// the file to be cleaned up.
     #[inline]
-    fn description(&self) -> &str {
-        "failed to persist temporary file"
+    pub fn from_file(file: File) -> TempFile {
+        TempFile { file: file, dir: None, path: None }
     }
+
+    /// Create a new temporary file and open it `count` times returning `count` independent
+    /// references to the same file.
+    ///
+    /// This can be useful if you want multiple seek positions in the same temporary file.
+    /// Additionally, this function guarantees that all of the returned temporary file objects
+    /// refer to the same underlying temporary file even in the presence of a pathological
+    /// temporary file cleaner.
+    ///
+    /// Each returned `TempFile` has its own file descriptor/handle (see `AsRawFd`/`AsRawHandle`)
+    /// obtained by opening the file again, not by duplicating an existing descriptor. That is what
+    /// makes their offsets independent; use `try_clone` if you want a shared offset instead.
     #[inline]
-    fn cause(&self) -> Option<&error::Error> {
-        Some(&self.error)
+    pub fn shared(count: usize) -> io::Result<Vec<TempFile>> {
+        Self::shared_in(&default_temp_dir(), count)
     }
-}
 
-impl NamedTempFile {
+    /// Same as `shared` but creates the file in the specified directory.
     #[inline]
-    fn inner(&self) -> &NamedTempFileInner {
-        self.0.as_ref().unwrap()
+    pub fn shared_in<P: AsRef<Path>>(dir: P, count: usize) -> io::Result<Vec<TempFile>> {
+        match Self::shared_best_effort(dir, count) {
+            (_, Some(err)) => Err(err),
+            (tmpfiles, None) => Ok(tmpfiles),
+        }
     }
 
-    #[inline]
-    fn inner_mut(&mut self) -> &mut NamedTempFileInner {
-        self.0.as_mut().unwrap()
+    /// Same as `shared_in` but, if opening one of the references fails (e.g., because the process
+    /// ran out of file descriptors), returns the references opened so far along with the error
+    /// instead of failing altogether.
+    ///
+    /// All returned references are valid, independent handles to the same temporary file. If
+    /// the file itself can't be created, the `Vec` is empty.
+    pub fn shared_best_effort<P: AsRef<Path>>(dir: P, count: usize)
+        -> (Vec<TempFile>, Option<io::Error>)
+    {
+        let dir = dir.as_ref();
+        let (files, error) = imp::create_shared_best_effort(dir, count);
+        let tmpfiles = files.into_iter().map(|f| {
+            TempFile { file: f, dir: Some(dir.to_path_buf()), path: None }
+        }).collect();
+        (tmpfiles, error)
     }
 
-    /// Create a new temporary file.
+    /// Same as `shared(2)` but returns the two independent references as a tuple (e.g., one for
+    /// writing and one for reading).
     #[inline]
-    pub fn new() -> io::Result<NamedTempFile> {
-        Self::new_in(&env::temp_dir())
+    pub fn shared_pair() -> io::Result<(TempFile, TempFile)> {
+        Self::shared_pair_in(&default_temp_dir())
     }
 
-    /// Create a new temporary file in the specified directory.
+    /// Same as `shared_pair` but creates the file in the specified directory.
+    pub fn shared_pair_in<P: AsRef<Path>>(dir: P) -> io::Result<(TempFile, TempFile)> {
+        let mut files = try!(Self::shared_in(dir, 2));
+        let second = files.pop().unwrap();
+        let first = files.pop().unwrap();
+        Ok((first, second))
+    }
+
+    /// Create a new temporary file and return `count` references to it that all **share** a
+    /// single offset.
+    ///
+    /// The file is created once and the remaining references are obtained with `try_clone`. Unlike
+    /// `shared`, reading, writing, or seeking through any of the returned handles moves the offset
+    /// of all of them (e.g., a write through one advances the read position of the others).
     #[inline]
-    pub fn new_in<P: AsRef<Path>>(dir: P) -> io::Result<NamedTempFile> {
-        loop {
-            let path = dir.as_ref().join(&util::tmpname());
-            return match imp::create_named(&path) {
-                Ok(file) => Ok(NamedTempFile(Some(NamedTempFileInner { path: path, file: file, }))),
-                Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
-                Err(e) => Err(e),
-            }
+    pub fn shared_dup(count: usize) -> io::Result<Vec<TempFile>> {
+        Self::shared_dup_in(&default_temp_dir(), count)
+    }
+
+    /// Same as `shared_dup` but creates the file in the specified directory.
+    pub fn shared_dup_in<P: AsRef<Path>>(dir: P, count: usize) -> io::Result<Vec<TempFile>> {
+        if count == 0 {
+            return Ok(vec![]);
         }
+        let first = try!(Self::new_in(dir));
+        let mut files: Vec<TempFile> = try!((1..count).map(|_| first.try_clone()).collect());
+        files.push(first);
+        Ok(files)
     }
 
     /// Queries metadata about the underlying file.
     #[inline]
     pub fn metadata(&self) -> io::Result<fs::Metadata> {
-        self.inner().file.metadata()
+        self.file.metadata()
     }
 
-    /// Truncate the file to `size` bytes.
+    /// Number of bytes in the file.
     #[inline]
-    pub fn set_len(&self, size: u64) -> io::Result<()> {
-        self.inner().file.set_len(size)
+    pub fn len(&self) -> io::Result<u64> {
+        self.metadata().map(|m| m.len())
     }
 
-    /// Get the temporary file's path.
+    /// Returns true if the file is empty.
     #[inline]
-    pub fn path(&self) -> &Path {
-        &self.inner().path
+    pub fn is_empty(&self) -> io::Result<bool> {
+        self.len().map(|l| l == 0)
     }
 
-    /// Close and remove the temporary file.
-    ///
-    /// Use this if you want to detect errors in deleting the file.
-    #[inline]
-    pub fn close(mut self) -> io::Result<()> {
-        let NamedTempFileInner { path, file } = self.0.take().unwrap();
-        drop(file);
-        fs::remove_file(path)
+    /// Truncate the file to `size` bytes.
+    #[inline(always)]
+    pub fn set_len(&self, size: u64) -> io::Result<()> {
+        self.file.set_len(size)
     }
 
-    /// Extract the path to the temporary file. Calling this will prevent the temporary file from
-    /// being automatically deleted.
+    /// Change the permissions of the underlying file.
+    ///
+    /// This works through the open handle so, on *nix, it works even though the file has no path.
     #[inline]
-    pub fn into_path(mut self) -> PathBuf {
-        let NamedTempFileInner { path, .. } = self.0.take().unwrap();
-        path
+    pub fn set_permissions(&self, perm: fs::Permissions) -> io::Result<()> {
+        self.file.set_permissions(perm)
     }
 
-    /// Persist the temporary file at the target path.
-    ///
-    /// If a file exists at the target path, persist will atomically replace it. If this method
-    /// fails, it will return `self` in the resulting PersistError.
+    /// Truncate the file to `size` bytes if (and only if) it's currently longer than that.
     ///
-    /// Note: Temporary files cannot be persisted across filesystems.
+    /// Unlike `set_len`, this never extends the file. Returns true if the file was truncated.
     #[inline]
-    pub fn persist<P: AsRef<Path>>(mut self, new_path: P) -> Result<File, PersistError> {
-        match fs::rename(&self.inner().path, new_path) {
-            Ok(_) => Ok(self.0.take().unwrap().file),
-            Err(e) => Err(PersistError { file: self, error: e }),
+    pub fn truncate_to_at_most(&self, size: u64) -> io::Result<bool> {
+        if try!(self.metadata()).len() <= size {
+            return Ok(false);
         }
+        try!(self.set_len(size));
+        Ok(true)
     }
-}
 
-impl Drop for NamedTempFile {
-    #[inline]
-    fn drop(&mut self) {
-        if let Some(NamedTempFileInner { file, path }) = self.0.take() {
-            drop(file);
-            let _ = fs::remove_file(path);
-        }
+    /// Attempt to sync all OS-internal metadata and data to disk.
+    ///
+    /// This is still meaningful for files that were never linked into the filesystem (Linux >=
+    /// 3.11) or have already been unlinked: the inode exists for as long as the file is open.
+    #[inline(always)]
+    pub fn sync_all(&self) -> io::Result<()> {
+        self.file.sync_all()
     }
-}
 
-impl Read for NamedTempFile {
-    #[inline]
-    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        self.inner_mut().file.read(buf)
+    /// Same as `sync_all` but doesn't necessarily sync metadata.
+    #[inline(always)]
+    pub fn sync_data(&self) -> io::Result<()> {
+        self.file.sync_data()
     }
-}
 
-impl Write for NamedTempFile {
+    /// Flush any buffered writes and then sync the file's data to disk (`sync_data`).
+    ///
+    /// `flush` alone only hands buffered data to the OS (and is a no-op for a plain file); it
+    /// doesn't make it survive a crash. Use this where durability matters.
     #[inline]
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    pub fn flush_sync(&mut self) -> io::Result<()> {
+        try!(self.flush());
+        self.sync_data()
+    }
+
+    /// Map the whole file into memory, read-write (requires the `memmap2` feature).
+    ///
+    /// The file is first extended to `min_len` bytes if it's shorter (mapping an empty file
+    /// fails). Writes through the mapping are writes to the file.
+    ///
+    /// # Safety
+    ///
+    /// The mapping isn't tied to the `TempFile`'s lifetime but it must not be used in ways that
+    /// conflict with the file: if the file is truncated while it's mapped (through this `TempFile`,
+    /// a handle from `shared`/`try_clone`, or another process), accessing the truncated part of the
+    /// mapping is undefined behavior (typically `SIGBUS`). See `memmap2::MmapMut::map_mut`.
+    #[cfg(feature = "memmap2")]
+    pub unsafe fn as_mmap_mut(&self, min_len: u64) -> io::Result<memmap2::MmapMut> {
+        if try!(self.len()) < min_len {
+            try!(self.set_len(min_len));
+        }
+        memmap2::MmapMut::map_mut(&self.file)
+    }
+
+    /// Returns true if the file currently has no path in the filesystem.
+    ///
+    /// * *nix: true if the file's link count is zero, which is normally the case: the file was
+    ///   either never linked (`O_TMPFILE` on Linux) or unlinked right after being created. It's
+    ///   false if unlinking was deferred (see `Builder::unlink_now`) or for a linked file wrapped
+    ///   with `from_file`.
+    /// * Windows: always false. Files marked `DeleteOnClose` keep their path until closed.
+    ///
+    /// Also returns false if the file's metadata can't be queried.
+    #[cfg(unix)]
+    #[inline]
+    pub fn is_anonymous(&self) -> bool {
+        use std::os::unix::fs::MetadataExt;
+        self.file.metadata().map(|m| m.nlink() == 0).unwrap_or(false)
+    }
+
+    /// Returns true if the file currently has no path in the filesystem.
+    ///
+    /// This is always false on Windows: files marked `DeleteOnClose` keep their path until closed.
+    #[cfg(windows)]
+    #[inline]
+    pub fn is_anonymous(&self) -> bool {
+        false
+    }
+
+    /// Convert the temporary file into an `OwnedFd`, e.g. to send it to another process over a
+    /// Unix socket (`SCM_RIGHTS`).
+    ///
+    /// The crate stops managing the file. It is already unlinked (or was never linked), so the
+    /// receiving process gets an anonymous file that the OS deletes once every descriptor to it is
+    /// closed. If unlinking was deferred (see `Builder::unlink_now`), the file is unlinked now.
+    #[cfg(unix)]
+    #[inline]
+    pub fn into_owned_fd(self) -> std::os::unix::io::OwnedFd {
+        self.into()
+    }
+
+    /// Get the filesystem's preferred I/O block size for this file (`st_blksize`).
+    ///
+    /// Buffers that are a multiple of this size make reads and writes most efficient.
+    #[cfg(unix)]
+    #[inline]
+    pub fn blksize(&self) -> io::Result<u64> {
+        use std::os::unix::fs::MetadataExt;
+        self.file.metadata().map(|m| m.blksize())
+    }
+
+    /// Get the preferred I/O block size for this file.
+    ///
+    /// Windows doesn't expose this so this always returns 4096, the most common cluster size.
+    #[cfg(windows)]
+    #[inline]
+    pub fn blksize(&self) -> io::Result<u64> {
+        Ok(4096)
+    }
+
+    /// Read from the file at the given offset without changing the current offset.
+    ///
+    /// Combined with `shared`, this lets multiple handles access disjoint regions of the same file.
+    #[cfg(unix)]
+    #[inline]
+    pub fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        use std::os::unix::fs::FileExt;
+        self.file.read_at(buf, offset)
+    }
+
+    /// Write to the file at the given offset without changing the current offset.
+    ///
+    /// Combined with `shared`, this lets multiple handles access disjoint regions of the same file.
+    #[cfg(unix)]
+    #[inline]
+    pub fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        use std::os::unix::fs::FileExt;
+        self.file.write_at(buf, offset)
+    }
+
+    /// Read from the file at the given offset.
+    ///
+    /// Unlike on *nix, this **does** move the current offset (to the end of the bytes read).
+    #[cfg(windows)]
+    #[inline]
+    pub fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        use std::os::windows::fs::FileExt;
+        self.file.seek_read(buf, offset)
+    }
+
+    /// Write to the file at the given offset.
+    ///
+    /// Unlike on *nix, this **does** move the current offset (to the end of the bytes written).
+    #[cfg(windows)]
+    #[inline]
+    pub fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        use std::os::windows::fs::FileExt;
+        self.file.seek_write(buf, offset)
+    }
+
+    /// Get the current offset without moving it.
+    #[inline]
+    pub fn position(&mut self) -> io::Result<u64> {
+        self.seek(SeekFrom::Current(0))
+    }
+
+    /// Flush any buffered writes and seek back to the start of the file, ready to read back what
+    /// was written.
+    ///
+    /// The flush is guaranteed to happen before the seek.
+    #[inline]
+    pub fn rewind_for_read(&mut self) -> io::Result<()> {
+        try!(self.flush());
+        self.seek(SeekFrom::Start(0)).map(|_| ())
+    }
+
+    /// Read the entire contents of the file, regardless of the current offset.
+    ///
+    /// The offset is restored to its prior position afterwards (unless an error occurs).
+    pub fn read_all(&mut self) -> io::Result<Vec<u8>> {
+        let pos = try!(self.seek(SeekFrom::Current(0)));
+        try!(self.seek(SeekFrom::Start(0)));
+        let mut buf = Vec::new();
+        try!(self.read_to_end(&mut buf));
+        try!(self.seek(SeekFrom::Start(pos)));
+        Ok(buf)
+    }
+
+    /// Feed the entire contents of the file, regardless of the current offset, to `hasher`.
+    ///
+    /// The file is read in 64 KiB chunks (so it's never loaded into memory at once) and each chunk
+    /// is passed to `Hasher::write`. Note that the result may therefore depend on how `hasher`
+    /// handles split writes. The offset is restored to its prior position afterwards (unless an
+    /// error occurs).
+    pub fn hash<H: Hasher>(&mut self, hasher: &mut H) -> io::Result<()> {
+        let pos = try!(self.position());
+        try!(self.seek(SeekFrom::Start(0)));
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            match self.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => hasher.write(&buf[..n]),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        try!(self.seek(SeekFrom::Start(pos)));
+        Ok(())
+    }
+
+    /// Read the entire contents of the file and truncate it, leaving it empty with the offset at
+    /// the start.
+    ///
+    /// Other handles to the same file (from `shared` or `try_clone`) observe the truncation. As
+    /// with any concurrent access, data written through them while this is running may be lost.
+    pub fn drain(&mut self) -> io::Result<Vec<u8>> {
+        try!(self.seek(SeekFrom::Start(0)));
+        let mut buf = Vec::new();
+        try!(self.read_to_end(&mut buf));
+        try!(self.set_len(0));
+        try!(self.seek(SeekFrom::Start(0)));
+        Ok(buf)
+    }
+
+    /// Wrap the file in a `BufReader`, reading from the current offset.
+    ///
+    /// This uses `BufReader`'s default buffer size (currently 8 KiB). To pick a size based on the
+    /// filesystem (e.g., a multiple of `blksize`), use `BufReader::with_capacity` directly.
+    #[inline]
+    pub fn into_buf_reader(self) -> BufReader<TempFile> {
+        BufReader::new(self)
+    }
+
+    /// Same as `into_buf_reader` but borrows the file.
+    ///
+    /// Reading through the `BufReader` moves the file's offset further than what was actually
+    /// consumed (buffered data is lost when the `BufReader` is dropped).
+    #[inline]
+    pub fn buf_reader(&mut self) -> BufReader<&mut TempFile> {
+        BufReader::new(self)
+    }
+
+    /// Rewind the file and read its entire contents into a `String`.
+    ///
+    /// Unlike `read_to_string`, this allocates a buffer of the file's current length upfront. The
+    /// length is only a hint: if the file changes size in the meantime, the result still contains
+    /// exactly what was read. Fails with `InvalidData` if the contents aren't valid UTF-8.
+    pub fn read_to_string_sized(&mut self) -> io::Result<String> {
+        let len = try!(self.len());
+        try!(self.seek(SeekFrom::Start(0)));
+        let mut buf = String::with_capacity(usize::try_from(len).unwrap_or(0));
+        try!(self.read_to_string(&mut buf));
+        Ok(buf)
+    }
+
+    /// Copy the entire contents of the file into `dst`, regardless of the current offset, returning
+    /// the number of bytes copied.
+    ///
+    /// The offset is restored to its prior position afterwards (unless an error occurs). This uses
+    /// `io::copy` and therefore always goes through a userspace buffer, even when `dst` is a file.
+    pub fn copy_to<W: Write>(&mut self, dst: &mut W) -> io::Result<u64> {
+        let pos = try!(self.position());
+        try!(self.seek(SeekFrom::Start(0)));
+        let copied = try!(io::copy(self, dst));
+        try!(self.seek(SeekFrom::Start(pos)));
+        Ok(copied)
+    }
+
+    /// Give the temporary file a (random) name in `dir`, turning it into a `NamedTempFile`.
+    ///
+    /// This tries, in order:
+    ///
+    /// * If the file still has a path (see `Builder::unlink_now` and `Builder::delete_on_close`),
+    ///   moving it to `dir`.
+    /// * On Linux, linking the unnamed file into `dir` with `linkat`. This only works for files
+    ///   opened with `O_TMPFILE` but *without* `O_EXCL`, i.e., files created with
+    ///   `Builder::linkable` (or wrapped with `from_file`). `dir` must be on the same filesystem.
+    /// * Otherwise, copying the contents into a new `NamedTempFile` in `dir`. The result is then a
+    ///   different file: other handles to this file (from `shared` or `try_clone`) don't see
+    ///   changes made through the `NamedTempFile`.
+    ///
+    /// The offset is preserved in all cases.
+    pub fn into_named_in<P: AsRef<Path>>(mut self, dir: P) -> io::Result<NamedTempFile> {
+        let dir = dir.as_ref();
+        if let Some(old_path) = self.path.take() {
+            let moved = util::create_helper(dir, &Builder::new(), |path| {
+                imp::persist_noclobber(&old_path, &path).map(|_| path)
+            });
+            match moved {
+                Ok(path) => {
+                    old_path.keep();
+                    return Ok(NamedTempFile::from_parts(self.file, path));
+                },
+                Err(_) => self.path = Some(old_path),
+            }
+        }
+        #[cfg(target_os = "linux")]
+        {
+            let linked = util::create_helper(dir, &Builder::new(), |path| {
+                imp::link_at(&self.file, &path).map(|_| path)
+            });
+            if let Ok(path) = linked {
+                return Ok(NamedTempFile::from_parts(self.file, path));
+            }
+        }
+        let pos = try!(self.position());
+        let mut named = try!(NamedTempFile::new_in(dir));
+        try!(self.copy_to(&mut named));
+        try!(named.seek(SeekFrom::Start(pos)));
+        Ok(named)
+    }
+
+    /// Create a new, independent temporary file holding a copy of this file's entire contents.
+    ///
+    /// Unlike `shared` and `try_clone`, the copy has its own storage: changes to one aren't
+    /// visible through the other. The copy is created in the same directory as this file (the
+    /// default temporary directory if unknown). Afterwards, this file's offset is unchanged and
+    /// the copy's offset is at the start.
+    pub fn fork(&mut self) -> io::Result<TempFile> {
+        let mut copy = match self.dir {
+            Some(ref dir) => try!(TempFile::new_in(dir)),
+            None => try!(TempFile::new()),
+        };
+        try!(self.copy_to(&mut copy));
+        try!(copy.seek(SeekFrom::Start(0)));
+        Ok(copy)
+    }
+
+    /// Create a new `NamedTempFile` in `dir` holding a copy of this file's entire contents.
+    ///
+    /// Like `fork`, but the copy is named and its directory is chosen by the caller (e.g., to be
+    /// on the same filesystem as the path it will be persisted to). This file's offset is
+    /// unchanged and the copy's offset is at the start. If copying fails, the copy is deleted.
+    pub fn clone_to_dir<P: AsRef<Path>>(&mut self, dir: P) -> io::Result<NamedTempFile> {
+        let mut copy = try!(NamedTempFile::new_in(dir));
+        try!(self.copy_to(&mut copy));
+        try!(copy.seek(SeekFrom::Start(0)));
+        Ok(copy)
+    }
+
+    /// Get the directory in which the temporary file was created.
+    ///
+    /// Returns `None` if this `TempFile` was created with `from_file` or `new_memfd`.
+    #[inline]
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_ref().map(|d| &**d)
+    }
+
+    /// Get a reference to the underlying file.
+    #[inline(always)]
+    pub fn as_file(&self) -> &File {
+        &self.file
+    }
+
+    /// Get a mutable reference to the underlying file.
+    #[inline(always)]
+    pub fn as_file_mut(&mut self) -> &mut File {
+        &mut self.file
+    }
+
+    /// Create a new handle to the same temporary file that **shares** this handle's offset.
+    ///
+    /// This duplicates the underlying file descriptor/handle so, unlike `reopen` and `shared`,
+    /// seeking, reading, or writing through one handle moves the offset of the other. Use `shared`
+    /// (or `reopen`) if you need independent seek positions.
+    #[inline]
+    pub fn try_clone(&self) -> io::Result<TempFile> {
+        self.file.try_clone().map(|f| TempFile { file: f, dir: self.dir.clone(), path: None })
+    }
+
+    /// Convert this temporary file into a read-only one.
+    ///
+    /// The returned `ReadOnlyTempFile` only implements `Read` and `Seek` so any attempt to write to
+    /// it is caught at compile time. Its offset starts at the beginning of the file.
+    ///
+    /// On Linux and Windows, the file is re-opened with a read-only access mode. Elsewhere (BSDs,
+    /// macOS, ...), the file can't be re-opened (see `reopen`) so the original read-write handle is
+    /// kept: only the type system prevents writes and there is **no** OS-level protection. Anything
+    /// that gets hold of the raw file descriptor can still write to the file.
+    #[inline]
+    pub fn into_read_only(self) -> io::Result<ReadOnlyTempFile> {
+        imp::into_read_only(self.file).map(|f| ReadOnlyTempFile(f))
+    }
+
+    /// Re-open the temporary file. The returned TempFile will refer to the same underlying
+    /// temporary file but will have an independent offset.
+    ///
+    /// This method is only available on windows and Linux, not FreeBSD/MacOS. Unfortunately, it is
+    /// impossible to reliably implement this method on those operating systems: the file is
+    /// unlinked as soon as it's created so there is no path left to re-open it by, and opening
+    /// `/dev/fd/N` merely duplicates the descriptor (sharing its offset, like `try_clone`).
+    ///
+    /// If you need your code to be cross-platform, please use `shared`/`shared_in` defined above.
+    ///
+    /// **Unstable**: This is platform specific and may go away in the future.
+    #[cfg(any(windows, target_os = "linux"))]
+    #[inline]
+    pub fn reopen(&self) -> io::Result<TempFile> {
+        imp::reopen(&self.file).map(|f| TempFile { file: f, dir: self.dir.clone(), path: None })
+    }
+}
+
+/// An iterator that lazily creates temporary files, returned by `TempFile::iter`/`iter_in`.
+#[derive(Debug)]
+pub struct TempFileIter {
+    dir: PathBuf,
+    done: bool,
+}
+
+impl Iterator for TempFileIter {
+    type Item = io::Result<TempFile>;
+
+    fn next(&mut self) -> Option<io::Result<TempFile>> {
+        if self.done {
+            return None;
+        }
+        let result = TempFile::new_in(&self.dir).map_err(io::Error::from);
+        self.done = result.is_err();
+        Some(result)
+    }
+}
+
+/// Wrap an already-open regular file, failing with `InvalidInput` if it isn't one.
+///
+/// As with `TempFile::from_file`, the file is *not* unlinked: the `TempFile` can't know its path.
+impl TryFrom<File> for TempFile {
+    type Error = io::Error;
+
+    fn try_from(file: File) -> io::Result<TempFile> {
+        if try!(file.metadata()).is_file() {
+            Ok(TempFile::from_file(file))
+        } else {
+            Err(io::Error::new(io::ErrorKind::InvalidInput, "not a regular file"))
+        }
+    }
+}
+
+/// Hand the temporary file to a child process (e.g., as its stdin).
+///
+/// The file is no longer managed by this crate: it is deleted by the OS once both the parent's
+/// `Stdio`/`Command` and the child process have closed it.
+impl From<TempFile> for Stdio {
+    #[inline]
+    fn from(tmpfile: TempFile) -> Stdio {
+        Stdio::from(tmpfile.file)
+    }
+}
+
+impl fmt::Debug for TempFile {
+    #[cfg(unix)]
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use std::os::unix::io::AsRawFd;
+        write!(f, "TempFile {{ fd: {} }}", self.file.as_raw_fd())
+    }
+
+    #[cfg(windows)]
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use std::os::windows::io::AsRawHandle;
+        write!(f, "TempFile {{ handle: {:?} }}", self.file.as_raw_handle())
+    }
+}
+
+impl Read for TempFile {
+    #[inline(always)]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+    #[inline(always)]
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut]) -> io::Result<usize> {
+        self.file.read_vectored(bufs)
+    }
+}
+
+impl Write for TempFile {
+    #[inline(always)]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+    #[inline(always)]
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> io::Result<usize> {
+        self.file.write_vectored(bufs)
+    }
+    #[inline(always)]
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Seek for TempFile {
+    #[inline(always)]
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.file.seek(pos)
+    }
+}
+
+/// Like `&File`, I/O through a shared reference uses (and moves) the file's single offset. Use
+/// `shared` to get independent offsets or positional I/O (`read_at`/`write_at`) to avoid the offset
+/// altogether.
+impl<'a> Read for &'a TempFile {
+    #[inline(always)]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&self.file).read(buf)
+    }
+}
+
+impl<'a> Write for &'a TempFile {
+    #[inline(always)]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&self.file).write(buf)
+    }
+    #[inline(always)]
+    fn flush(&mut self) -> io::Result<()> {
+        (&self.file).flush()
+    }
+}
+
+impl<'a> Seek for &'a TempFile {
+    #[inline(always)]
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        (&self.file).seek(pos)
+    }
+}
+
+#[cfg(unix)]
+impl std::os::unix::io::AsRawFd for TempFile {
+    #[inline(always)]
+    fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+        self.file.as_raw_fd()
+    }
+}
+
+#[cfg(windows)]
+impl std::os::windows::io::AsRawHandle for TempFile {
+    #[inline(always)]
+    fn as_raw_handle(&self) -> std::os::windows::io::RawHandle {
+        self.file.as_raw_handle()
+    }
+}
+
+/// Transfer ownership of the file descriptor to the caller, who becomes responsible for closing
+/// it. The file itself is already unlinked (or was never linked) so the OS deletes it once the
+/// descriptor and any copies of it are closed.
+#[cfg(unix)]
+impl std::os::unix::io::IntoRawFd for TempFile {
+    #[inline(always)]
+    fn into_raw_fd(self) -> std::os::unix::io::RawFd {
+        self.file.into_raw_fd()
+    }
+}
+
+/// Transfer ownership of the handle to the caller, who becomes responsible for closing it. The
+/// file is marked DeleteOnClose so it is deleted once the handle and any copies of it are closed.
+#[cfg(windows)]
+impl std::os::windows::io::IntoRawHandle for TempFile {
+    #[inline(always)]
+    fn into_raw_handle(self) -> std::os::windows::io::RawHandle {
+        self.file.into_raw_handle()
+    }
+}
+
+/// Transfer ownership of the file descriptor to an `OwnedFd` (see `TempFile::into_owned_fd`).
+#[cfg(unix)]
+impl From<TempFile> for std::os::unix::io::OwnedFd {
+    #[inline]
+    fn from(tmpfile: TempFile) -> std::os::unix::io::OwnedFd {
+        tmpfile.file.into()
+    }
+}
+
+/// A read-only unnamed temporary file, created by `TempFile::into_read_only`.
+///
+/// The underlying `File` isn't exposed as it could be used to write to the file.
+pub struct ReadOnlyTempFile(File);
+
+impl ReadOnlyTempFile {
+    /// Number of bytes in the file.
+    #[inline]
+    pub fn len(&self) -> io::Result<u64> {
+        self.0.metadata().map(|m| m.len())
+    }
+
+    /// Query metadata about the underlying file.
+    #[inline]
+    pub fn metadata(&self) -> io::Result<fs::Metadata> {
+        self.0.metadata()
+    }
+}
+
+impl fmt::Debug for ReadOnlyTempFile {
+    #[cfg(unix)]
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use std::os::unix::io::AsRawFd;
+        write!(f, "ReadOnlyTempFile {{ fd: {} }}", self.0.as_raw_fd())
+    }
+
+    #[cfg(windows)]
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use std::os::windows::io::AsRawHandle;
+        write!(f, "ReadOnlyTempFile {{ handle: {:?} }}", self.0.as_raw_handle())
+    }
+}
+
+impl Read for ReadOnlyTempFile {
+    #[inline(always)]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Seek for ReadOnlyTempFile {
+    #[inline(always)]
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
+
+#[cfg(unix)]
+impl std::os::unix::io::AsRawFd for ReadOnlyTempFile {
+    #[inline(always)]
+    fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+#[cfg(windows)]
+impl std::os::windows::io::AsRawHandle for ReadOnlyTempFile {
+    #[inline(always)]
+    fn as_raw_handle(&self) -> std::os::windows::io::RawHandle {
+        self.0.as_raw_handle()
+    }
+}
+
+/// A named temporary file.
+///
+/// This variant is *NOT* secure/reliable in the presence of a pathological temporary file cleaner.
+///
+/// NamedTempFiles are deleted on drop. As rust doesn't guarantee that a struct will ever be
+/// dropped, these temporary files will not be deleted on abort, resource leak, early exit, etc.
+///
+/// Please use TempFile unless you absolutely need a named file.
+///
+pub struct NamedTempFile(Option<NamedTempFileInner>);
+
+struct NamedTempFileInner {
+    file: File,
+    path: PathBuf,
+    // Whether `path` is in the registry of `new_tracked` files.
+    tracked: bool,
+}
+
+impl fmt::Debug for NamedTempFile {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NamedTempFile({:?})", self.0.as_ref().unwrap().path)
+    }
+}
+
+/// Temporary files are compared by path, not by contents: two `NamedTempFile`s are equal if (and
+/// only if) they have the same path.
+impl PartialEq for NamedTempFile {
+    #[inline]
+    fn eq(&self, other: &NamedTempFile) -> bool {
+        self.path() == other.path()
+    }
+}
+
+impl Eq for NamedTempFile {}
+
+/// Hashes the temporary file's path, consistent with `PartialEq`.
+impl Hash for NamedTempFile {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.path().hash(state)
+    }
+}
+
+/// Error returned when persisting a temporary file fails
+#[derive(Debug)]
+pub struct PersistError {
+    /// The underlying IO error.
+    pub error: io::Error,
+    /// The temporary file that couldn't be persisted.
+    pub file: NamedTempFile,
+    /// The path at which the temporary file was to be persisted.
+    pub path: PathBuf,
+}
+
+impl From<PersistError> for io::Error {
+    #[inline]
+    fn from(error: PersistError) -> io::Error {
+        error.error
+    }
+}
+
+impl fmt::Display for PersistError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "failed to persist temporary file to {:?}: {}", self.path, self.error)
+    }
+}
+
+impl error::Error for PersistError {
+    #[inline]
+    fn description(&self) -> &str {
+        "failed to persist temporary file"
+    }
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        Some(&self.error)
+    }
+}
+
+/// Error returned when closing (deleting) a temporary file fails
+#[derive(Debug)]
+pub struct CloseError {
+    /// The underlying IO error.
+    pub error: io::Error,
+    /// The path of the temporary file that couldn't be deleted.
+    pub path: PathBuf,
+}
+
+impl From<CloseError> for io::Error {
+    #[inline]
+    fn from(error: CloseError) -> io::Error {
+        error.error
+    }
+}
+
+impl fmt::Display for CloseError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "failed to delete temporary file {:?}: {}", self.path, self.error)
+    }
+}
+
+impl error::Error for CloseError {
+    #[inline]
+    fn description(&self) -> &str {
+        "failed to delete temporary file"
+    }
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        Some(&self.error)
+    }
+}
+
+/// Error returned when creating a temporary file fails
+#[derive(Debug)]
+pub struct CreateError {
+    /// The underlying IO error.
+    pub error: io::Error,
+    /// The directory in which the temporary file was to be created.
+    pub dir: PathBuf,
+}
+
+impl From<CreateError> for io::Error {
+    #[inline]
+    fn from(error: CreateError) -> io::Error {
+        error.error
+    }
+}
+
+impl fmt::Display for CreateError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "failed to create temporary file in {:?}: {}", self.dir, self.error)
+    }
+}
+
+impl error::Error for CreateError {
+    #[inline]
+    fn description(&self) -> &str {
+        "failed to create temporary file"
+    }
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        Some(&self.error)
+    }
+}
+
+impl NamedTempFile {
+    #[inline]
+    fn inner(&self) -> &NamedTempFileInner {
+        self.0.as_ref().unwrap()
+    }
+
+    #[inline]
+    fn inner_mut(&mut self) -> &mut NamedTempFileInner {
+        self.0.as_mut().unwrap()
+    }
+
+    // Take ownership of the file and path, which are no longer managed by this `NamedTempFile`.
+    #[inline]
+    fn take_inner(&mut self) -> NamedTempFileInner {
+        let inner = self.0.take().unwrap();
+        if inner.tracked {
+            untrack(&inner.path);
+        }
+        inner
+    }
+
+    /// Create a new temporary file whose path is also recorded in a process-wide registry, so
+    /// that it can be deleted by `cleanup_tracked` even if its destructor never runs.
+    ///
+    /// The path is removed from the registry when the `NamedTempFile` stops managing it (when
+    /// it's dropped, closed, persisted, kept, or converted with `into_path`, `into_file`, or
+    /// `into_temp_path`).
+    #[inline]
+    pub fn new_tracked() -> io::Result<NamedTempFile> {
+        Self::new_tracked_in(&default_temp_dir())
+    }
+
+    /// Same as `new_tracked` but creates the file in the specified directory.
+    pub fn new_tracked_in<P: AsRef<Path>>(dir: P) -> io::Result<NamedTempFile> {
+        let mut tmpfile = try!(Self::new_in(dir));
+        track(tmpfile.path());
+        tmpfile.inner_mut().tracked = true;
+        Ok(tmpfile)
+    }
+
+    /// Create a new temporary file.
+    #[inline]
+    pub fn new() -> io::Result<NamedTempFile> {
+        Self::new_in(&default_temp_dir())
+    }
+
+    /// Create a new temporary file in the specified directory.
+    #[inline]
+    pub fn new_in<P: AsRef<Path>>(dir: P) -> io::Result<NamedTempFile> {
+        Builder::new().tempfile_in(dir)
+    }
+
+    /// Create a new temporary file in the same directory as `target`.
+    ///
+    /// This is the right place for a file that will later be `persist`ed to `target`: being in the
+    /// same directory, it's on the same filesystem, so the rename can't fail with a cross-device
+    /// error. A relative `target` without a directory component uses the current directory. Fails
+    /// with `InvalidInput` if `target` has no parent (e.g., it's a filesystem root).
+    pub fn new_beside<P: AsRef<Path>>(target: P) -> io::Result<NamedTempFile> {
+        match target.as_ref().parent() {
+            Some(dir) if dir == Path::new("") => Self::new_in("."),
+            Some(dir) => Self::new_in(dir),
+            None => Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                       "target path has no parent directory")),
+        }
+    }
+
+    /// Reserve a unique path by exclusively creating an empty temporary file and closing it.
+    ///
+    /// The returned `TempPath` deletes the file when dropped. This is meant for handing a path to
+    /// a program that insists on opening the file itself: the name can't be claimed by anyone else
+    /// between its generation and that program's `open`.
+    #[inline]
+    pub fn reserve() -> io::Result<TempPath> {
+        Self::reserve_in(&default_temp_dir())
+    }
+
+    /// Same as `reserve` but creates the file in the specified directory.
+    #[inline]
+    pub fn reserve_in<P: AsRef<Path>>(dir: P) -> io::Result<TempPath> {
+        Self::new_in(dir).map(NamedTempFile::into_temp_path)
+    }
+
+    /// Create a `NamedTempFile` from an already-open file and its path.
+    ///
+    /// The resulting `NamedTempFile` takes ownership of both and will delete `path` when dropped,
+    /// even though the file wasn't created by this crate. It's up to the caller to make sure
+    /// `path` actually refers to `file`.
+    #[inline]
+    pub fn from_parts(file: File, path: PathBuf) -> NamedTempFile {
+        NamedTempFile(Some(NamedTempFileInner { path: path, file: file, tracked: false }))
+    }
+
+    /// Create a new temporary file holding `contents`, with the offset at the start.
+    ///
+    /// If writing fails, the file is deleted.
+    #[inline]
+    pub fn with_contents(contents: &[u8]) -> io::Result<NamedTempFile> {
+        Self::with_contents_in(&default_temp_dir(), contents)
+    }
+
+    /// Same as `with_contents` but creates the file in the specified directory.
+    pub fn with_contents_in<P: AsRef<Path>>(dir: P, contents: &[u8]) -> io::Result<NamedTempFile> {
+        let mut tmpfile = try!(Self::new_in(dir));
+        try!(tmpfile.write_all(contents));
+        try!(tmpfile.rewind_for_read());
+        Ok(tmpfile)
+    }
+
+    /// Create a new temporary file whose name ends with `suffix`.
+    ///
+    /// The suffix is appended literally after the random part of the name so, to create a file
+    /// with an extension, include the dot (e.g. `".json"`).
+    #[inline]
+    pub fn with_suffix(suffix: &str) -> io::Result<NamedTempFile> {
+        Builder::new().suffix(suffix).tempfile()
+    }
+
+    /// Same as `with_suffix` but creates the file in the specified directory.
+    #[inline]
+    pub fn with_suffix_in<P: AsRef<Path>>(dir: P, suffix: &str) -> io::Result<NamedTempFile> {
+        Builder::new().suffix(suffix).tempfile_in(dir)
+    }
+
+    /// Create a new temporary file whose name starts with `prefix` and ends with `suffix`.
+    ///
+    /// Only the random part in between is regenerated on name collisions. Fails with
+    /// `InvalidInput` if either affix contains a path separator.
+    #[inline]
+    pub fn with_prefix_suffix(prefix: &str, suffix: &str) -> io::Result<NamedTempFile> {
+        Builder::new().prefix(prefix).suffix(suffix).tempfile()
+    }
+
+    /// Same as `with_prefix_suffix` but creates the file in the specified directory.
+    #[inline]
+    pub fn with_prefix_suffix_in<P: AsRef<Path>>(dir: P, prefix: &str, suffix: &str)
+        -> io::Result<NamedTempFile>
+    {
+        Builder::new().prefix(prefix).suffix(suffix).tempfile_in(dir)
+    }
+
+    /// Create `count` temporary files in `dir`, all named `prefix` followed by random characters.
+    ///
+    /// Either all of the files are created or none are: if creating any of them fails, the ones
+    /// already created are deleted before the error is returned.
+    pub fn batch_in<P: AsRef<Path>>(dir: P, prefix: &str, count: usize)
+        -> io::Result<Vec<NamedTempFile>>
+    {
+        let dir = dir.as_ref();
+        let mut builder = Builder::new();
+        builder.prefix(prefix);
+        let mut files = Vec::with_capacity(count);
+        for _ in 0..count {
+            // On error, dropping `files` deletes every file created so far.
+            files.push(try!(builder.tempfile_in(dir)));
+        }
+        Ok(files)
+    }
+
+    /// Queries metadata about the underlying file.
+    #[inline]
+    pub fn metadata(&self) -> io::Result<fs::Metadata> {
+        self.inner().file.metadata()
+    }
+
+    /// Returns true if the file is empty.
+    #[inline]
+    pub fn is_empty(&self) -> io::Result<bool> {
+        self.metadata().map(|m| m.len() == 0)
+    }
+
+    /// Truncate the file to `size` bytes.
+    #[inline]
+    pub fn set_len(&self, size: u64) -> io::Result<()> {
+        self.inner().file.set_len(size)
+    }
+
+    /// Truncate the file to `size` bytes if (and only if) it's currently longer than that.
+    ///
+    /// Unlike `set_len`, this never extends the file. Returns true if the file was truncated.
+    #[inline]
+    pub fn truncate_to_at_most(&self, size: u64) -> io::Result<bool> {
+        if try!(self.metadata()).len() <= size {
+            return Ok(false);
+        }
+        try!(self.set_len(size));
+        Ok(true)
+    }
+
+    /// Attempt to sync all OS-internal metadata and data to disk.
+    ///
+    /// Call this before `persist` if the data needs to be durable once the file has been renamed
+    /// into place.
+    #[inline]
+    pub fn sync_all(&self) -> io::Result<()> {
+        self.inner().file.sync_all()
+    }
+
+    /// Same as `sync_all` but doesn't necessarily sync metadata.
+    #[inline]
+    pub fn sync_data(&self) -> io::Result<()> {
+        self.inner().file.sync_data()
+    }
+
+    /// Flush any buffered writes and then sync the file's data to disk (`sync_data`).
+    ///
+    /// `flush` alone only hands buffered data to the OS (and is a no-op for a plain file); it
+    /// doesn't make it survive a crash. Use this where durability matters.
+    #[inline]
+    pub fn flush_sync(&mut self) -> io::Result<()> {
+        try!(self.flush());
+        self.sync_data()
+    }
+
+    /// Get the temporary file's path.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.inner().path
+    }
+
+    /// Get a reference to the underlying file.
+    #[inline]
+    pub fn as_file(&self) -> &File {
+        &self.inner().file
+    }
+
+    /// Change the permissions of the underlying file.
+    ///
+    /// Use this to loosen (or tighten) the permissions before `persist`; see also
+    /// `persist_with_mode`.
+    #[inline]
+    pub fn set_permissions(&self, perm: fs::Permissions) -> io::Result<()> {
+        self.inner().file.set_permissions(perm)
+    }
+
+    /// Get a mutable reference to the underlying file.
+    #[inline]
+    pub fn as_file_mut(&mut self) -> &mut File {
+        &mut self.inner_mut().file
+    }
+
+    /// Get the current offset without moving it.
+    #[inline]
+    pub fn position(&mut self) -> io::Result<u64> {
+        self.seek(SeekFrom::Current(0))
+    }
+
+    /// Flush any buffered writes and seek back to the start of the file, ready to read back what
+    /// was written.
+    ///
+    /// The flush is guaranteed to happen before the seek.
+    #[inline]
+    pub fn rewind_for_read(&mut self) -> io::Result<()> {
+        try!(self.flush());
+        self.seek(SeekFrom::Start(0)).map(|_| ())
+    }
+
+    /// Check that the temporary file still exists at its path.
+    ///
+    /// This returns false if the path no longer exists or if it now refers to a different file
+    /// than the one this `NamedTempFile` has open, e.g. because a temporary file cleaner deleted
+    /// the file and someone else created a new one in its place. If this returns false, the path
+    /// should no longer be trusted.
+    ///
+    /// This compares device and inode numbers on *nix and volume serial numbers and file indices
+    /// on Windows.
+    #[inline]
+    pub fn exists_on_disk(&self) -> bool {
+        imp::same_file_at(&self.inner().file, &self.inner().path).unwrap_or(false)
+    }
+
+    /// Check that `other` refers to the same file as this temporary file.
+    ///
+    /// This is the anchor for sharing a temporary file with another process by path: one side
+    /// creates the `NamedTempFile` (exclusively, so nobody else can have created it first) and
+    /// passes its path along, the other side opens the path, and the opened file is then checked
+    /// against the original (e.g. after being passed back over a Unix socket, or by comparing
+    /// with a handle opened via `reopen`). If this returns false, the path was swapped by someone
+    /// else and the file must not be trusted.
+    ///
+    /// Like `exists_on_disk`, this compares device and inode numbers on *nix and volume serial
+    /// numbers and file indices on Windows.
+    #[inline]
+    pub fn verify_same_file(&self, other: &File) -> io::Result<bool> {
+        imp::same_file(&self.inner().file, other)
+    }
+
+    /// Give the temporary file the same SELinux security context as `reference` (Linux only).
+    ///
+    /// A temporary file is labeled according to the directory it's created in, so a file that is
+    /// later persisted somewhere else (or replaces an existing file) can end up mislabeled. Call
+    /// this before `persist`, with the file being replaced or the target directory as `reference`,
+    /// to copy its `security.selinux` extended attribute (akin to running `restorecon` with
+    /// `reference`'s label). Setting a label may require privileges, depending on the policy.
+    ///
+    /// This does nothing if `reference` has no label (e.g., SELinux is disabled or the filesystem
+    /// doesn't support labels).
+    #[cfg(target_os = "linux")]
+    #[inline]
+    pub fn copy_security_context_from<P: AsRef<Path>>(&self, reference: P) -> io::Result<()> {
+        imp::copy_security_context(&self.inner().file, reference.as_ref())
+    }
+
+    /// Get the directory in which the temporary file was created.
+    ///
+    /// Returns `None` if the path has no parent, which can only happen for paths passed to
+    /// `from_parts` (e.g., `/`).
+    #[inline]
+    pub fn dir(&self) -> Option<&Path> {
+        self.path().parent()
+    }
+
+    /// Rename the temporary file within its directory while keeping it managed.
+    ///
+    /// Unlike `persist`, the file remains a temporary file: it is still deleted (under its new
+    /// name) when this `NamedTempFile` is dropped. `new_name` must be a plain file name (no path
+    /// separators, `.`, or `..`) or this fails with `InvalidInput`. This never replaces an existing
+    /// file; if one exists with that name, this fails with `AlreadyExists`.
+    pub fn rename_to(&mut self, new_name: &OsStr) -> io::Result<()> {
+        if Path::new(new_name).file_name() != Some(new_name) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "new temporary file name must be a plain file name"));
+        }
+        let new_path = match self.dir() {
+            Some(dir) => dir.join(new_name),
+            None => return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                              "temporary file has no parent directory")),
+        };
+        try!(imp::persist_noclobber(&self.inner().path, &new_path));
+        if self.inner().tracked {
+            untrack(&self.inner().path);
+            track(&new_path);
+        }
+        self.inner_mut().path = new_path;
+        Ok(())
+    }
+
+    /// Open the temporary file again by its path, returning a `File` with an independent offset.
+    ///
+    /// This is the intended way to get a second reader/writer while the temporary file is alive.
+    /// The returned `File` doesn't own the temporary file: it is still deleted when this
+    /// `NamedTempFile` is dropped (on *nix, the reopened `File` stays usable after that).
+    ///
+    /// As this goes through the filesystem, it is subject to the same caveats regarding
+    /// pathological temporary file cleaners as `NamedTempFile` itself.
+    #[inline]
+    pub fn reopen(&self) -> io::Result<File> {
+        OpenOptions::new().read(true).write(true).open(self.path())
+    }
+
+    /// Close and remove the temporary file.
+    ///
+    /// Use this if you want to detect errors in deleting the file. On failure, the returned
+    /// `CloseError` holds the path of the file that couldn't be deleted (it converts into an
+    /// `io::Error` for use with `try!`).
+    #[inline]
+    pub fn close(mut self) -> Result<(), CloseError> {
+        let NamedTempFileInner { path, file, .. } = self.take_inner();
+        drop(file);
+        match fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(CloseError { error: e, path: path }),
+        }
+    }
+
+    /// Extract the path to the temporary file. Calling this will prevent the temporary file from
+    /// being automatically deleted.
+    ///
+    /// Use `into_temp_path` to close the file but keep the automatic deletion.
+    #[inline]
+    pub fn into_path(mut self) -> PathBuf {
+        let NamedTempFileInner { path, .. } = self.take_inner();
+        path
+    }
+
+    /// Close the file, returning a `TempPath` that still deletes it when dropped.
+    #[inline]
+    pub fn into_temp_path(mut self) -> TempPath {
+        let NamedTempFileInner { path, .. } = self.take_inner();
+        TempPath::from_path(path)
+    }
+
+    /// Delete the temporary file's path and return the open file.
+    ///
+    /// On *nix, the path is unlinked immediately and the file lives on, anonymously, until the
+    /// returned `File` is closed. On Windows, the file is marked for deletion but its path stays
+    /// visible (and can't be reused) until the returned `File` is closed.
+    ///
+    /// Like the destructor, this ignores errors in deleting the file.
+    #[inline]
+    pub fn into_file(mut self) -> File {
+        let NamedTempFileInner { path, file, .. } = self.take_inner();
+        let _ = fs::remove_file(path);
+        file
+    }
+
+    /// Keep the temporary file in place, disabling automatic deletion, and return the open file
+    /// along with its path.
+    ///
+    /// Unlike `into_path`, this doesn't close the file. The `Result` mirrors `persist`; on error,
+    /// the temporary file is returned in the `PersistError` and will still be deleted on drop.
+    #[inline]
+    pub fn keep(mut self) -> Result<(File, PathBuf), PersistError> {
+        let NamedTempFileInner { file, path, .. } = self.take_inner();
+        Ok((file, path))
+    }
+
+    /// Persist the temporary file at the target path.
+    ///
+    /// If a file exists at the target path, persist will atomically replace it. If this method
+    /// fails, it will return `self` in the resulting PersistError.
+    ///
+    /// The persisted file keeps the temporary file's permissions which, on *nix, default to `0o600`
+    /// (see `Builder::mode`). Use `persist_with_mode` to change them.
+    ///
+    /// If `new_path` is an existing directory, the file is moved *into* it, keeping its (random)
+    /// temporary file name, i.e. it's persisted at `new_path.join(self.path().file_name())`. In
+    /// that case, the `path` of a `PersistError` is that full path.
+    ///
+    /// Note: Temporary files cannot be persisted across filesystems.
+    #[inline]
+    pub fn persist<P: AsRef<Path>>(mut self, new_path: P) -> Result<File, PersistError> {
+        let new_path = self.persist_target(new_path.as_ref());
+        match fs::rename(&self.inner().path, &new_path) {
+            Ok(_) => Ok(self.take_inner().file),
+            Err(e) => Err(PersistError { file: self, error: e, path: new_path }),
+        }
+    }
+
+    // Resolve the path `persist` moves the file to.
+    fn persist_target(&self, new_path: &Path) -> PathBuf {
+        if new_path.is_dir() {
+            new_path.join(self.path().file_name().unwrap())
+        } else {
+            new_path.to_path_buf()
+        }
+    }
+
+    /// Persist the temporary file at the target path with the given permission bits (unix only).
+    ///
+    /// The permissions are changed *before* the file is renamed so the file never appears at the
+    /// target path with the temporary file's (restrictive) permissions. Unlike `Builder::mode`,
+    /// `mode` is not subject to the umask. Otherwise, this behaves like `persist`.
+    #[cfg(unix)]
+    pub fn persist_with_mode<P: AsRef<Path>>(self, new_path: P, mode: u32)
+        -> Result<File, PersistError>
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Err(e) = self.set_permissions(fs::Permissions::from_mode(mode)) {
+            let path = self.persist_target(new_path.as_ref());
+            return Err(PersistError { file: self, error: e, path: path });
+        }
+        self.persist(new_path)
+    }
+
+    /// Persist the temporary file at the target path and make sure the result survives a crash.
+    ///
+    /// The file's data is synced to disk, the file is renamed (atomically replacing any existing
+    /// file, like `persist`), and then the directory containing the target is synced so that the
+    /// rename itself is durable.
+    ///
+    /// If syncing the file or renaming it fails, `self` is returned in the resulting PersistError.
+    /// If only the final directory sync fails, the file has already been renamed: the returned
+    /// PersistError holds a `NamedTempFile` at the *new* path that, like any other temporary file,
+    /// is deleted when dropped. Retry `persist_durable` with the same path or call `keep` on it.
+    ///
+    /// Like `persist`, if `new_path` is a directory, the file is moved into it.
+    ///
+    /// On Windows, there is no way to sync a directory; NTFS journals the rename instead.
+    pub fn persist_durable<P: AsRef<Path>>(self, new_path: P) -> Result<File, PersistError> {
+        let new_path = &self.persist_target(new_path.as_ref());
+        if let Err(e) = self.sync_all() {
+            return Err(PersistError { file: self, error: e, path: new_path.to_path_buf() });
+        }
+        let file = try!(self.persist(new_path));
+        let dir = match new_path.parent() {
+            Some(dir) if dir != Path::new("") => dir,
+            _ => Path::new("."),
+        };
+        match imp::sync_dir(dir) {
+            Ok(()) => Ok(file),
+            Err(e) => Err(PersistError {
+                file: NamedTempFile::from_parts(file, new_path.to_path_buf()),
+                error: e,
+                path: new_path.to_path_buf(),
+            }),
+        }
+    }
+
+    /// Persist the temporary file at the target path, copying it if it can't be renamed because the
+    /// target is on a different filesystem.
+    ///
+    /// This first tries `persist`. If that fails because the target is on another filesystem, the
+    /// contents are copied into a new temporary file next to the target, synced to disk, and then
+    /// atomically renamed into place; the original temporary file is then deleted. The copy
+    /// fallback is not atomic with respect to the original temporary file but the target path is
+    /// only ever replaced atomically.
+    ///
+    /// On success, the returned `File` refers to the persisted file (which, in the fallback case,
+    /// is the copy). If this method fails, it will return `self` in the resulting PersistError.
+    pub fn persist_with_copy_fallback<P: AsRef<Path>>(mut self, new_path: P)
+        -> Result<File, PersistError>
+    {
+        let new_path = self.persist_target(new_path.as_ref());
+        match fs::rename(&self.inner().path, &new_path) {
+            Ok(_) => Ok(self.take_inner().file),
+            Err(ref e) if imp::crosses_devices(e) => self.persist_by_copy(&new_path),
+            Err(e) => Err(PersistError { file: self, error: e, path: new_path }),
+        }
+    }
+
+    // Persist a copy of the temporary file at `new_path`, returning `self` (with its offset
+    // unchanged) on failure.
+    fn persist_by_copy(mut self, new_path: &Path) -> Result<File, PersistError> {
+        match self.copy_into_place(new_path) {
+            Ok(file) => Ok(file),
+            Err(e) => Err(PersistError { file: self, error: e, path: new_path.to_path_buf() }),
+        }
+    }
+
+    fn copy_into_place(&mut self, new_path: &Path) -> io::Result<File> {
+        let pos = try!(self.seek(SeekFrom::Current(0)));
+        let dir = new_path.parent().unwrap_or(Path::new(""));
+        let result = NamedTempFile::new_in(dir).and_then(|mut copy| {
+            try!(self.seek(SeekFrom::Start(0)));
+            try!(io::copy(self, &mut copy));
+            try!(copy.sync_all());
+            copy.persist(new_path).map_err(|e| e.error)
+        });
+        if result.is_err() {
+            let _ = self.seek(SeekFrom::Start(pos));
+        }
+        result
+    }
+
+    /// Persist the temporary file at the target path iff no file exists there.
+    ///
+    /// If a file exists at the target path, fail with `AlreadyExists`. If this method fails, it
+    /// will return `self` in the resulting PersistError.
+    ///
+    /// Note: Temporary files cannot be persisted across filesystems.
+    #[inline]
+    pub fn persist_noclobber<P: AsRef<Path>>(mut self, new_path: P) -> Result<File, PersistError> {
+        let new_path = new_path.as_ref();
+        match imp::persist_noclobber(&self.inner().path, new_path) {
+            Ok(_) => Ok(self.take_inner().file),
+            Err(e) => Err(PersistError { file: self, error: e, path: new_path.to_path_buf() }),
+        }
+    }
+}
+
+impl Drop for NamedTempFile {
+    #[inline]
+    fn drop(&mut self) {
+        if self.0.is_some() {
+            let NamedTempFileInner { file, path, .. } = self.take_inner();
+            drop(file);
+            if let Err(e) = fs::remove_file(&path) {
+                cleanup_failed(&path, &e);
+            }
+        }
+    }
+}
+
+/// Hand the temporary file to a child process (e.g., as its stdin).
+///
+/// This deletes the file's path first (see `NamedTempFile::into_file`) and the file is no longer
+/// managed by this crate. On *nix, the data is freed once the child process closes the file; on
+/// Windows, the path only disappears at that point.
+impl From<NamedTempFile> for Stdio {
+    #[inline]
+    fn from(tmpfile: NamedTempFile) -> Stdio {
+        Stdio::from(tmpfile.into_file())
+    }
+}
+
+impl Read for NamedTempFile {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner_mut().file.read(buf)
+    }
+    #[inline]
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut]) -> io::Result<usize> {
+        self.inner_mut().file.read_vectored(bufs)
+    }
+}
+
+impl Write for NamedTempFile {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         self.inner_mut().file.write(buf)
     }
     #[inline]
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> io::Result<usize> {
+        self.inner_mut().file.write_vectored(bufs)
+    }
+    #[inline]
     fn flush(&mut self) -> io::Result<()> {
         self.inner_mut().file.flush()
     }
@@ -343,3 +2008,341 @@ impl std::os::windows::io::AsRawHandle for NamedTempFile {
         self.inner().file.as_raw_handle()
     }
 }
+
+/// Create a `NamedTempFile` or `TempDir` with a custom name, or a `TempFile` with custom options.
+///
+/// The generated file name is `{prefix}{random}{suffix}` where `random` is `rand_bytes`
+/// alphanumeric characters. By default, the prefix is `.`, the suffix is empty, and 6 random
+/// characters are used. Naming options don't apply to `TempFile`s as they have no name.
+///
+/// ```no_run
+/// use tempfile::Builder;
+///
+/// let tmpfile = Builder::new().prefix("image-").suffix(".png").tempfile().unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Builder<'a, 'b> {
+    prefix: &'a str,
+    suffix: &'b str,
+    random_len: usize,
+    max_retries: usize,
+    retry_timeout: Option<Duration>,
+    seed: Option<Arc<AtomicU64>>,
+    counter: Option<Arc<AtomicU64>>,
+    alphabet: Option<Vec<u8>>,
+    opts: imp::Options,
+    #[cfg(unix)]
+    unlink_now: bool,
+    #[cfg(windows)]
+    delete_on_close: bool,
+}
+
+impl<'a, 'b> Default for Builder<'a, 'b> {
+    #[inline]
+    fn default() -> Builder<'a, 'b> {
+        Builder {
+            prefix: util::DEFAULT_PREFIX,
+            suffix: "",
+            random_len: util::NUM_RAND_CHARS,
+            max_retries: std::usize::MAX,
+            retry_timeout: None,
+            seed: None,
+            counter: None,
+            alphabet: None,
+            opts: imp::Options::default(),
+            #[cfg(unix)]
+            unlink_now: true,
+            #[cfg(windows)]
+            delete_on_close: true,
+        }
+    }
+}
+
+impl<'a, 'b> Builder<'a, 'b> {
+    /// Create a new builder with the default settings.
+    #[inline]
+    pub fn new() -> Builder<'a, 'b> {
+        Builder::default()
+    }
+
+    /// Set the file name prefix.
+    ///
+    /// The prefix must not contain a path separator.
+    #[inline]
+    pub fn prefix(&mut self, prefix: &'a str) -> &mut Builder<'a, 'b> {
+        self.prefix = prefix;
+        self
+    }
+
+    /// Set the file name suffix.
+    ///
+    /// The suffix must not contain a path separator.
+    #[inline]
+    pub fn suffix(&mut self, suffix: &'b str) -> &mut Builder<'a, 'b> {
+        self.suffix = suffix;
+        self
+    }
+
+    /// Set the number of random characters in the file name.
+    ///
+    /// Each character is drawn from `[0-9a-zA-Z]` (~5.95 bits of entropy per character). Increase
+    /// this if you create many temporary files in the same directory and want to avoid name
+    /// collisions (and the resulting retries).
+    #[inline]
+    pub fn rand_bytes(&mut self, rand: usize) -> &mut Builder<'a, 'b> {
+        self.random_len = rand;
+        self
+    }
+
+    /// Generate names from a deterministic RNG seeded with `seed`, **for testing only**.
+    ///
+    /// Each name generated by this builder (including retries after collisions) advances the RNG
+    /// so a given seed always yields the same sequence of names. This is useful to get
+    /// reproducible paths in tests. Like `counter`, the RNG state is shared by clones of this
+    /// builder.
+    ///
+    /// **Warning**: Seeded names are predictable and therefore NOT secure. An attacker (or a
+    /// pathological temporary file cleaner) can guess them and create files in their place. Never
+    /// use this outside of tests.
+    #[inline]
+    pub fn rng_seed_for_testing(&mut self, seed: u64) -> &mut Builder<'a, 'b> {
+        self.seed = Some(Arc::new(AtomicU64::new(seed)));
+        self
+    }
+
+    /// Stop retrying with new names once `timeout` has elapsed since the first attempt.
+    ///
+    /// Once the timeout is reached, creation fails with a `TimedOut` error. If `max_retries` is
+    /// also set, whichever limit is hit first applies. The timeout is only checked between
+    /// attempts: it doesn't interrupt a slow `open`. By default, there is no timeout.
+    #[inline]
+    pub fn retry_timeout(&mut self, timeout: Duration) -> &mut Builder<'a, 'b> {
+        self.retry_timeout = Some(timeout);
+        self
+    }
+
+    /// Name files with an incrementing counter, starting at `start`, instead of random characters.
+    ///
+    /// Names are the prefix, the counter zero-padded to (at least) four digits, and the suffix,
+    /// e.g. `test-0001.log`. On a collision, the counter is bumped and creation retried. The
+    /// counter is shared by clones of this builder (e.g., one per thread) so they never generate
+    /// the same name.
+    ///
+    /// **Security**: Predictable names are *not* safe in shared directories: anyone can create
+    /// (or, with a pathological temporary file cleaner, replace) the next file in the sequence.
+    /// This is meant for local debugging only. Overrides `rng_seed_for_testing`.
+    #[inline]
+    pub fn counter(&mut self, start: u64) -> &mut Builder<'a, 'b> {
+        self.counter = Some(Arc::new(AtomicU64::new(start)));
+        self
+    }
+
+    /// Set the characters the random part of names is made of.
+    ///
+    /// Defaults to ASCII letters and digits. For example, pass `b"0123456789abcdef"` for
+    /// lowercase-only names. The alphabet must be non-empty ASCII and must not contain NUL or a
+    /// path separator; otherwise, creating a file fails with `InvalidInput`. Smaller alphabets
+    /// make collisions more likely so consider increasing `rand_bytes` accordingly.
+    #[inline]
+    pub fn rand_alphabet(&mut self, alphabet: &[u8]) -> &mut Builder<'a, 'b> {
+        self.alphabet = Some(alphabet.to_vec());
+        self
+    }
+
+    /// Set the maximum number of times to retry with a new name if a file with the generated name
+    /// already exists.
+    ///
+    /// Once exhausted, creation fails with an `AlreadyExists` error. By default, creation is
+    /// retried indefinitely.
+    #[inline]
+    pub fn max_retries(&mut self, retries: usize) -> &mut Builder<'a, 'b> {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Set the permission bits of the created file (unix only).
+    ///
+    /// The mode is passed to `open` and is therefore subject to the process's umask. Defaults to
+    /// `0o600`.
+    #[cfg(unix)]
+    #[inline]
+    pub fn mode(&mut self, mode: u32) -> &mut Builder<'a, 'b> {
+        self.opts.mode = mode;
+        self
+    }
+
+    /// Open the file in append mode.
+    ///
+    /// In append mode, every write goes to the end of the file regardless of the current offset:
+    /// seeking still affects reads but not where data is written.
+    #[inline]
+    pub fn append(&mut self, append: bool) -> &mut Builder<'a, 'b> {
+        self.opts.append = append;
+        self
+    }
+
+    /// Whether the file should be closed when executing another program.
+    ///
+    /// This is true by default so temporary files don't leak into child processes. Set it to
+    /// false to let child processes inherit the file, e.g. when passing its descriptor to a child
+    /// explicitly. On *nix this controls `O_CLOEXEC`; on Windows it controls whether the handle is
+    /// inheritable.
+    #[inline]
+    pub fn cloexec(&mut self, cloexec: bool) -> &mut Builder<'a, 'b> {
+        self.opts.cloexec = cloexec;
+        self
+    }
+
+    /// Whether to create unnamed temporary files with `O_TMPFILE` (linux only).
+    ///
+    /// By default, `O_TMPFILE` is used and, if the kernel or the filesystem doesn't support it,
+    /// the crate transparently falls back on creating a file and immediately unlinking it (as on
+    /// other *nix systems). Pass `false` to always use the fallback.
+    #[cfg(target_os = "linux")]
+    #[inline]
+    pub fn use_o_tmpfile(&mut self, o_tmpfile: bool) -> &mut Builder<'a, 'b> {
+        self.opts.o_tmpfile = o_tmpfile;
+        self
+    }
+
+    /// Whether to open temporary files with `O_DIRECT`, bypassing the page cache (linux only).
+    ///
+    /// Direct I/O comes with alignment requirements: the buffer addresses, lengths, and file
+    /// offsets of all reads and writes must be multiples of the filesystem's logical block size
+    /// (usually 512 bytes or 4 KiB; `blksize` is always a safe choice). Misaligned I/O fails with
+    /// `EINVAL`. Some filesystems (e.g., older versions of `tmpfs`) don't support `O_DIRECT` at
+    /// all, in which case creating the file fails with `EINVAL` rather than falling back on
+    /// buffered I/O.
+    #[cfg(target_os = "linux")]
+    #[inline]
+    pub fn direct(&mut self, direct: bool) -> &mut Builder<'a, 'b> {
+        self.opts.direct = direct;
+        self
+    }
+
+    /// Whether unnamed temporary files created with `O_TMPFILE` may later be given a name (linux
+    /// only).
+    ///
+    /// By default, `O_TMPFILE` files are opened with `O_EXCL`, which guarantees that they never
+    /// show up in the filesystem. Pass `true` to omit it so that `TempFile::into_named_in` can
+    /// link the file into a directory instead of copying its contents. This has no effect if
+    /// `O_TMPFILE` isn't used (see `use_o_tmpfile`).
+    #[cfg(target_os = "linux")]
+    #[inline]
+    pub fn linkable(&mut self, linkable: bool) -> &mut Builder<'a, 'b> {
+        self.opts.linkable = linkable;
+        self
+    }
+
+    /// Whether unnamed temporary files are unlinked as soon as they're created (unix only).
+    ///
+    /// By default, an unnamed temporary file never has a visible path (or only has one for an
+    /// instant). Pass `false` to keep the file's path around until the `TempFile` is dropped
+    /// (e.g., to be able to `ls` it while debugging). This disables `O_TMPFILE` on Linux.
+    ///
+    /// **Security**: With `false`, the `TempFile` is exposed to the same pathological temporary
+    /// file cleaners as a `NamedTempFile`: its path can be deleted and replaced while it's alive,
+    /// in which case the destructor deletes the replacement. Only use this during development.
+    #[cfg(unix)]
+    #[inline]
+    pub fn unlink_now(&mut self, unlink_now: bool) -> &mut Builder<'a, 'b> {
+        self.unlink_now = unlink_now;
+        self
+    }
+
+    /// Whether unnamed temporary files are marked `DeleteOnClose` (windows only).
+    ///
+    /// By default, the OS deletes an unnamed temporary file once all handles to it are closed.
+    /// However, depending on their share mode, other processes may be unable to open a file
+    /// marked `DeleteOnClose`. Pass `false` to create a normal file instead, deleted by path when
+    /// the `TempFile` is dropped (named temporary files are never marked `DeleteOnClose`).
+    ///
+    /// **Warning**: With `false`, the file is leaked if the destructor doesn't run (e.g., if the
+    /// process is killed or aborts).
+    #[cfg(windows)]
+    #[inline]
+    pub fn delete_on_close(&mut self, delete_on_close: bool) -> &mut Builder<'a, 'b> {
+        self.delete_on_close = delete_on_close;
+        self
+    }
+
+    #[cfg(unix)]
+    #[inline]
+    fn defer_delete(&self) -> bool {
+        !self.unlink_now
+    }
+
+    #[cfg(windows)]
+    #[inline]
+    fn defer_delete(&self) -> bool {
+        !self.delete_on_close
+    }
+
+    /// Generate a file name the same way this builder would for a `NamedTempFile` or `TempDir`,
+    /// without creating anything.
+    ///
+    /// The name never contains a path separator (this fails with `InvalidInput` if the prefix or
+    /// suffix does) and, apart from the prefix and suffix, only consists of ASCII alphanumeric
+    /// characters. Nothing guarantees that no file with this name exists.
+    #[inline]
+    pub fn make_name(&self) -> io::Result<OsString> {
+        util::make_name(self)
+    }
+
+    /// Create an unnamed temporary file in the default temporary directory.
+    #[inline]
+    pub fn unnamed(&self) -> io::Result<TempFile> {
+        self.unnamed_in(&default_temp_dir())
+    }
+
+    /// Create an unnamed temporary file in the specified directory.
+    #[inline]
+    pub fn unnamed_in<P: AsRef<Path>>(&self, dir: P) -> io::Result<TempFile> {
+        let dir = dir.as_ref();
+        if self.defer_delete() {
+            let mut named = try!(self.tempfile_in(dir));
+            let NamedTempFileInner { file, path, .. } = named.take_inner();
+            return Ok(TempFile {
+                file: file,
+                dir: Some(dir.to_path_buf()),
+                path: Some(TempPath::from_path(path)),
+            });
+        }
+        let file = try!(imp::create(dir, self));
+        Ok(TempFile { file: file, dir: Some(dir.to_path_buf()), path: None })
+    }
+
+    /// Create a named temporary file in the default temporary directory.
+    ///
+    /// Fails with `InvalidInput` if the prefix or suffix contains a path separator.
+    #[inline]
+    pub fn tempfile(&self) -> io::Result<NamedTempFile> {
+        self.tempfile_in(&default_temp_dir())
+    }
+
+    /// Create a named temporary file in the specified directory.
+    ///
+    /// Fails with `InvalidInput` if the prefix or suffix contains a path separator.
+    pub fn tempfile_in<P: AsRef<Path>>(&self, dir: P) -> io::Result<NamedTempFile> {
+        util::create_helper(dir.as_ref(), self, |path| {
+            imp::create_named(&path, &self.opts).map(|file| {
+                NamedTempFile(Some(NamedTempFileInner { path: path, file: file, tracked: false }))
+            })
+        })
+    }
+
+    /// Create a temporary directory in the default temporary directory.
+    ///
+    /// Fails with `InvalidInput` if the prefix or suffix contains a path separator.
+    #[inline]
+    pub fn tempdir(&self) -> io::Result<TempDir> {
+        self.tempdir_in(&default_temp_dir())
+    }
+
+    /// Create a temporary directory in the specified directory.
+    ///
+    /// Fails with `InvalidInput` if the prefix or suffix contains a path separator.
+    pub fn tempdir_in<P: AsRef<Path>>(&self, dir: P) -> io::Result<TempDir> {
+        util::create_helper(dir.as_ref(), self, dir::create)
+    }
+}
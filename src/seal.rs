@@ -0,0 +1,55 @@
+use std::ops::{BitOr, BitOrAssign};
+
+/// A set of seals that can be applied to a memfd-backed `TempFile` (see `TempFile::add_seals`).
+///
+/// Seals restrict the operations allowed on the file for *everyone* with access to it, which makes
+/// it possible to hand an immutable file to another process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SealFlags(u32);
+
+impl SealFlags {
+    /// No seals.
+    pub const EMPTY: SealFlags = SealFlags(0);
+    /// Prevent further seals from being added.
+    pub const SEAL: SealFlags = SealFlags(0x0001);
+    /// Prevent the file from shrinking.
+    pub const SHRINK: SealFlags = SealFlags(0x0002);
+    /// Prevent the file from growing.
+    pub const GROW: SealFlags = SealFlags(0x0004);
+    /// Prevent writes to the file's contents.
+    pub const WRITE: SealFlags = SealFlags(0x0008);
+
+    /// Get the raw `F_SEAL_*` bits.
+    #[inline]
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Create a set of seals from raw `F_SEAL_*` bits.
+    #[inline]
+    pub fn from_bits(bits: u32) -> SealFlags {
+        SealFlags(bits)
+    }
+
+    /// Returns true if all of the seals in `other` are also in `self`.
+    #[inline]
+    pub fn contains(&self, other: SealFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for SealFlags {
+    type Output = SealFlags;
+
+    #[inline]
+    fn bitor(self, other: SealFlags) -> SealFlags {
+        SealFlags(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for SealFlags {
+    #[inline]
+    fn bitor_assign(&mut self, other: SealFlags) {
+        self.0 |= other.0;
+    }
+}
@@ -0,0 +1,116 @@
+use std::io::{self, Read, Write, Seek, SeekFrom};
+use std::path::Path;
+use std::fs::File;
+use std::cmp;
+use std::fmt;
+
+use TempFile;
+
+/// An unnamed temporary file that overwrites its contents with zeros before it's released.
+///
+/// On drop, the file's current length is recorded, that many zero bytes are written over it from
+/// the start, the zeros are synced to disk, and the file is then truncated to zero bytes before
+/// being closed (and deleted by the OS, as with any `TempFile`).
+///
+/// This is a best-effort measure against casual recovery of sensitive data. It is *not*
+/// forensic-grade: journaling and copy-on-write filesystems, SSD wear leveling, swap, and the page
+/// cache can all keep copies of the data around.
+pub struct SecureTempFile(TempFile);
+
+impl SecureTempFile {
+    /// Create a new secure temporary file.
+    #[inline]
+    pub fn new() -> io::Result<SecureTempFile> {
+        TempFile::new().map(SecureTempFile).map_err(io::Error::from)
+    }
+
+    /// Create a new secure temporary file in the specified directory.
+    #[inline]
+    pub fn new_in<P: AsRef<Path>>(dir: P) -> io::Result<SecureTempFile> {
+        TempFile::new_in(dir).map(SecureTempFile).map_err(io::Error::from)
+    }
+
+    /// Number of bytes in the file.
+    #[inline]
+    pub fn len(&self) -> io::Result<u64> {
+        self.0.len()
+    }
+
+    /// Truncate the file to `size` bytes.
+    ///
+    /// Note: Truncated data is *not* zeroed first.
+    #[inline]
+    pub fn set_len(&self, size: u64) -> io::Result<()> {
+        self.0.set_len(size)
+    }
+
+    /// Get a reference to the underlying file.
+    #[inline]
+    pub fn as_file(&self) -> &File {
+        self.0.as_file()
+    }
+
+    /// Overwrite the file's contents with zeros and sync them to disk, keeping its length and
+    /// offset.
+    ///
+    /// This is the first step of what happens on drop; use it to wipe the data while still
+    /// holding on to the file.
+    pub fn zero(&mut self) -> io::Result<()> {
+        let pos = try!(self.0.seek(SeekFrom::Current(0)));
+        let len = try!(self.0.len());
+        try!(self.0.seek(SeekFrom::Start(0)));
+        let zeros = [0u8; 4096];
+        let mut remaining = len;
+        while remaining > 0 {
+            let n = cmp::min(remaining, zeros.len() as u64) as usize;
+            try!(self.0.write_all(&zeros[..n]));
+            remaining -= n as u64;
+        }
+        try!(self.0.sync_data());
+        self.0.seek(SeekFrom::Start(pos)).map(|_| ())
+    }
+
+    fn wipe(&mut self) -> io::Result<()> {
+        try!(self.zero());
+        self.0.set_len(0)
+    }
+}
+
+impl Drop for SecureTempFile {
+    #[inline]
+    fn drop(&mut self) {
+        let _ = self.wipe();
+    }
+}
+
+impl fmt::Debug for SecureTempFile {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SecureTempFile({:?})", self.0)
+    }
+}
+
+impl Read for SecureTempFile {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for SecureTempFile {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl Seek for SecureTempFile {
+    #[inline]
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
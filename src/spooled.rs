@@ -0,0 +1,118 @@
+use std::io::{self, Cursor, Read, Write, Seek, SeekFrom};
+use std::fmt;
+
+use TempFile;
+
+enum SpooledInner {
+    InMemory(Cursor<Vec<u8>>),
+    OnDisk(TempFile),
+}
+
+/// A temporary file that is buffered in memory until it grows larger than `max_size`.
+///
+/// Once the data (or the offset, after a seek) exceeds `max_size` bytes, the buffered contents are
+/// copied into a `TempFile` and all further I/O goes to that file. This is analogous to Python's
+/// `SpooledTemporaryFile`.
+pub struct SpooledTempFile {
+    max_size: usize,
+    inner: SpooledInner,
+}
+
+impl SpooledTempFile {
+    /// Create a new spooled temporary file that rolls over to disk once it exceeds `max_size`
+    /// bytes.
+    #[inline]
+    pub fn new(max_size: usize) -> SpooledTempFile {
+        SpooledTempFile {
+            max_size: max_size,
+            inner: SpooledInner::InMemory(Cursor::new(Vec::new())),
+        }
+    }
+
+    /// Returns true if the file has been rolled over to disk.
+    #[inline]
+    pub fn is_rolled(&self) -> bool {
+        match self.inner {
+            SpooledInner::InMemory(_) => false,
+            SpooledInner::OnDisk(_) => true,
+        }
+    }
+
+    /// Roll the file over to disk, regardless of its current size.
+    ///
+    /// The buffered contents are copied into a new `TempFile` and the offset is preserved. This
+    /// does nothing if the file has already been rolled over.
+    pub fn roll(&mut self) -> io::Result<()> {
+        let file = match self.inner {
+            SpooledInner::InMemory(ref cursor) => {
+                let mut file = try!(TempFile::new());
+                try!(file.write_all(cursor.get_ref()));
+                try!(file.seek(SeekFrom::Start(cursor.position())));
+                file
+            },
+            SpooledInner::OnDisk(_) => return Ok(()),
+        };
+        self.inner = SpooledInner::OnDisk(file);
+        Ok(())
+    }
+
+    // Roll over if the in-memory buffer would have to extend past `end`.
+    #[inline]
+    fn roll_if_past(&mut self, end: u64) -> io::Result<()> {
+        if !self.is_rolled() && end > self.max_size as u64 {
+            self.roll()
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl fmt::Debug for SpooledTempFile {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SpooledTempFile {{ max_size: {}, rolled: {} }}", self.max_size, self.is_rolled())
+    }
+}
+
+impl Read for SpooledTempFile {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.inner {
+            SpooledInner::InMemory(ref mut cursor) => cursor.read(buf),
+            SpooledInner::OnDisk(ref mut file) => file.read(buf),
+        }
+    }
+}
+
+impl Write for SpooledTempFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let end = match self.inner {
+            SpooledInner::InMemory(ref cursor) => cursor.position() + buf.len() as u64,
+            SpooledInner::OnDisk(_) => 0,
+        };
+        try!(self.roll_if_past(end));
+        match self.inner {
+            SpooledInner::InMemory(ref mut cursor) => cursor.write(buf),
+            SpooledInner::OnDisk(ref mut file) => file.write(buf),
+        }
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        match self.inner {
+            SpooledInner::InMemory(ref mut cursor) => cursor.flush(),
+            SpooledInner::OnDisk(ref mut file) => file.flush(),
+        }
+    }
+}
+
+impl Seek for SpooledTempFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match self.inner {
+            SpooledInner::InMemory(ref mut cursor) => try!(cursor.seek(pos)),
+            SpooledInner::OnDisk(ref mut file) => return file.seek(pos),
+        };
+        try!(self.roll_if_past(pos));
+        Ok(pos)
+    }
+}
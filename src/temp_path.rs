@@ -0,0 +1,78 @@
+use std::io;
+use std::fs;
+use std::fmt;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use cleanup_failed;
+
+/// A path to a temporary file that is deleted, if it exists, when it goes out of scope.
+///
+/// Unlike `NamedTempFile`, a `TempPath` doesn't keep the file open. This is useful when the file
+/// needs to be opened later or handed to another program by path. Get one with
+/// `NamedTempFile::into_temp_path`.
+///
+/// As a `TempPath` only has a path, it's subject to the same caveats regarding pathological
+/// temporary file cleaners as `NamedTempFile`, and then some: nothing stops the file at the path
+/// from being replaced while no handle to it is open.
+pub struct TempPath(Option<PathBuf>);
+
+impl TempPath {
+    /// Take ownership of `path`; it will be deleted when the `TempPath` is dropped.
+    #[inline]
+    pub fn from_path(path: PathBuf) -> TempPath {
+        TempPath(Some(path))
+    }
+
+    /// Delete the file at the path.
+    ///
+    /// Use this if you want to detect errors in deleting the file. A missing file isn't an error.
+    #[inline]
+    pub fn close(mut self) -> io::Result<()> {
+        match fs::remove_file(self.0.take().unwrap()) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Disarm the automatic deletion and return the path.
+    #[inline]
+    pub fn keep(mut self) -> PathBuf {
+        self.0.take().unwrap()
+    }
+}
+
+impl Deref for TempPath {
+    type Target = Path;
+
+    #[inline]
+    fn deref(&self) -> &Path {
+        self.0.as_ref().unwrap()
+    }
+}
+
+impl AsRef<Path> for TempPath {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        self
+    }
+}
+
+impl fmt::Debug for TempPath {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TempPath({:?})", &**self)
+    }
+}
+
+impl Drop for TempPath {
+    #[inline]
+    fn drop(&mut self) {
+        if let Some(path) = self.0.take() {
+            match fs::remove_file(&path) {
+                Err(ref e) if e.kind() != io::ErrorKind::NotFound => cleanup_failed(&path, e),
+                _ => (),
+            }
+        }
+    }
+}
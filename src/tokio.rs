@@ -0,0 +1,130 @@
+//! Asynchronous temporary files for use with Tokio (requires the `tokio` feature).
+
+use std::io::{self, SeekFrom};
+use std::fs::File;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio_crate::fs::File as TokioFile;
+use tokio_crate::io::{AsyncRead, AsyncWrite, AsyncSeek, ReadBuf};
+use tokio_crate::task::{self, JoinHandle};
+
+use {imp, default_temp_dir, Builder};
+
+/// An unnamed temporary file that performs its I/O on Tokio's blocking pool.
+///
+/// This is the asynchronous counterpart of `TempFile`: the file is created exactly like a
+/// `TempFile` (and is therefore deleted by the OS once closed) but creation happens on a
+/// blocking thread and the file implements `AsyncRead`, `AsyncWrite`, and `AsyncSeek` so it
+/// never blocks the executor.
+#[derive(Debug)]
+pub struct AsyncTempFile {
+    file: TokioFile,
+}
+
+/// Future returned by `AsyncTempFile::new` and `AsyncTempFile::new_in`.
+///
+/// Nothing happens until it's first polled, from within a Tokio runtime.
+#[derive(Debug)]
+pub struct CreateAsyncTempFile(CreateState);
+
+#[derive(Debug)]
+enum CreateState {
+    Idle(Option<PathBuf>),
+    Running(JoinHandle<io::Result<File>>),
+}
+
+impl AsyncTempFile {
+    /// Create a new temporary file.
+    ///
+    /// The returned future must be polled from within a Tokio runtime.
+    #[inline]
+    pub fn new() -> CreateAsyncTempFile {
+        Self::new_in(default_temp_dir())
+    }
+
+    /// Create a new temporary file in the specified directory.
+    ///
+    /// The returned future must be polled from within a Tokio runtime.
+    #[inline]
+    pub fn new_in<P: AsRef<Path>>(dir: P) -> CreateAsyncTempFile {
+        CreateAsyncTempFile(CreateState::Idle(Some(dir.as_ref().to_path_buf())))
+    }
+
+    /// Get a reference to the underlying file.
+    #[inline]
+    pub fn as_file(&self) -> &TokioFile {
+        &self.file
+    }
+
+    /// Get a mutable reference to the underlying file.
+    #[inline]
+    pub fn as_file_mut(&mut self) -> &mut TokioFile {
+        &mut self.file
+    }
+}
+
+impl Future for CreateAsyncTempFile {
+    type Output = io::Result<AsyncTempFile>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<AsyncTempFile>> {
+        // Only spawn once polled: there may be no runtime when the future is created.
+        if let CreateState::Idle(ref mut dir) = self.0 {
+            let dir = dir.take().expect("CreateAsyncTempFile polled after completion");
+            self.0 = CreateState::Running(task::spawn_blocking(move || {
+                imp::create(&dir, &Builder::new())
+            }));
+        }
+        let handle = match self.0 {
+            CreateState::Running(ref mut handle) => handle,
+            CreateState::Idle(_) => unreachable!(),
+        };
+        match Pin::new(handle).poll(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Ok(Ok(file))) => {
+                Poll::Ready(Ok(AsyncTempFile { file: TokioFile::from_std(file) }))
+            },
+            Poll::Ready(Ok(Err(e))) => Poll::Ready(Err(e)),
+            Poll::Ready(Err(e)) => Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, e))),
+        }
+    }
+}
+
+impl AsyncRead for AsyncTempFile {
+    #[inline]
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context, buf: &mut ReadBuf)
+        -> Poll<io::Result<()>>
+    {
+        Pin::new(&mut self.file).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for AsyncTempFile {
+    #[inline]
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context, buf: &[u8])
+        -> Poll<io::Result<usize>>
+    {
+        Pin::new(&mut self.file).poll_write(cx, buf)
+    }
+    #[inline]
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.file).poll_flush(cx)
+    }
+    #[inline]
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.file).poll_shutdown(cx)
+    }
+}
+
+impl AsyncSeek for AsyncTempFile {
+    #[inline]
+    fn start_seek(mut self: Pin<&mut Self>, pos: SeekFrom) -> io::Result<()> {
+        Pin::new(&mut self.file).start_seek(pos)
+    }
+    #[inline]
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<u64>> {
+        Pin::new(&mut self.file).poll_complete(cx)
+    }
+}
@@ -1,23 +1,188 @@
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
 use ::rand;
 use ::rand::Rng;
 use std::ffi::CString;
-use std::path::Path;
+use std::path::{self, Path, PathBuf};
 use std::io;
+use std::fs;
+use std::time::Instant;
+use std::sync::atomic::{AtomicU64, Ordering};
+use Builder;
 
-pub fn tmpname() -> OsString {
-    let mut bytes = vec!['.' as u8; 7];
-    rand::thread_rng().fill_bytes(&mut bytes[1..]);
+pub const DEFAULT_PREFIX: &'static str = ".";
+pub const NUM_RAND_CHARS: usize = 6;
+pub const DEFAULT_ALPHABET: &'static [u8] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
 
-    for byte in bytes[1..].iter_mut() {
-        *byte = match *byte % 62 {
-            v @ 0...9 => (v + '0' as u8),
-            v @ 10...35 => (v - 10 + 'a' as u8),
-            v @ 36...61 => (v - 36 + 'A' as u8),
-            _ => unreachable!(),
+pub fn tmpname(prefix: &str, suffix: &str, rand_len: usize) -> OsString {
+    let mut bytes = vec![0u8; rand_len];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    name_from_bytes(prefix, suffix, bytes, DEFAULT_ALPHABET)
+}
+
+/// Generate a name according to `builder`, using its counter or seeded RNG if it has one.
+pub fn builder_name(builder: &Builder) -> OsString {
+    if let Some(ref counter) = builder.counter {
+        let n = counter.fetch_add(1, Ordering::SeqCst);
+        return OsString::from(format!("{}{:04}{}", builder.prefix, n, builder.suffix));
+    }
+    let mut bytes = vec![0u8; builder.random_len];
+    match builder.seed {
+        Some(ref state) => seeded_fill(state, &mut bytes),
+        None => rand::thread_rng().fill_bytes(&mut bytes),
+    }
+    let alphabet = match builder.alphabet {
+        Some(ref alphabet) => alphabet,
+        None => DEFAULT_ALPHABET,
+    };
+    name_from_bytes(builder.prefix, builder.suffix, bytes, alphabet)
+}
+
+// SplitMix64. This is *not* cryptographically secure; it's only used for deterministic names.
+fn seeded_fill(state: &AtomicU64, bytes: &mut [u8]) {
+    const GAMMA: u64 = 0x9E3779B97F4A7C15;
+    for chunk in bytes.chunks_mut(8) {
+        // `fetch_add` wraps around, like `wrapping_add`.
+        let mut z = state.fetch_add(GAMMA, Ordering::SeqCst).wrapping_add(GAMMA);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z = z ^ (z >> 31);
+        for (i, byte) in chunk.iter_mut().enumerate() {
+            *byte = (z >> (i * 8)) as u8;
+        }
+    }
+}
+
+fn name_from_bytes(prefix: &str, suffix: &str, bytes: Vec<u8>, alphabet: &[u8]) -> OsString {
+    let mut name = String::with_capacity(prefix.len() + bytes.len() + suffix.len());
+    name.push_str(prefix);
+    for byte in bytes {
+        name.push(alphabet[byte as usize % alphabet.len()] as char);
+    }
+    name.push_str(suffix);
+    OsString::from(name)
+}
+
+/// Make sure that a name fragment can't escape the directory it is joined onto.
+pub fn check_affix(affix: &str) -> io::Result<()> {
+    if affix.chars().any(path::is_separator) {
+        Err(io::Error::new(io::ErrorKind::InvalidInput,
+                           "temporary file prefix/suffix contained a path separator"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Make sure that a custom alphabet only produces valid, single-component file names.
+pub fn check_alphabet(alphabet: &Option<Vec<u8>>) -> io::Result<()> {
+    if let Some(ref alphabet) = *alphabet {
+        if alphabet.is_empty() || alphabet.iter().any(|&b| {
+            b == 0 || !b.is_ascii() || path::is_separator(b as char)
+        }) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "temporary file name alphabet must be non-empty ASCII \
+                                       without NUL or path separators"));
+        }
+    }
+    Ok(())
+}
+
+/// Make sure that a generated name refers to a new entry in the directory it is joined onto.
+///
+/// `dir.join("")` and `dir.join(".")` are `dir` itself (and `..` its parent), which always exist:
+/// retrying would never succeed.
+pub fn check_name(name: &OsStr) -> io::Result<()> {
+    if name.is_empty() || name == "." || name == ".." {
+        Err(io::Error::new(io::ErrorKind::InvalidInput,
+                           "temporary file name must not be empty, \".\" or \"..\""))
+    } else {
+        Ok(())
+    }
+}
+
+/// Generate a single name according to `builder`.
+pub fn make_name(builder: &Builder) -> io::Result<OsString> {
+    try!(check_affix(builder.prefix));
+    try!(check_affix(builder.suffix));
+    try!(check_alphabet(&builder.alphabet));
+    let name = builder_name(builder);
+    try!(check_name(&name));
+    Ok(name)
+}
+
+/// Repeatedly call `f` with a fresh path in `base`, named according to `builder`, until it stops
+/// failing with `AlreadyExists` or the builder's retry limit or timeout is reached.
+pub fn create_helper<F, R>(base: &Path, builder: &Builder, mut f: F) -> io::Result<R>
+    where F: FnMut(PathBuf) -> io::Result<R>
+{
+    try!(check_affix(builder.prefix));
+    try!(check_affix(builder.suffix));
+    try!(check_alphabet(&builder.alphabet));
+    let start = Instant::now();
+    let mut retries = 0;
+    loop {
+        let name = builder_name(builder);
+        try!(check_name(&name));
+        return match f(base.join(&name)) {
+            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                if builder.retry_timeout.map_or(false, |timeout| start.elapsed() >= timeout) {
+                    Err(io::Error::new(io::ErrorKind::TimedOut,
+                                       "timed out looking for an unused temporary file name"))
+                } else if retries >= builder.max_retries {
+                    Err(io::Error::new(io::ErrorKind::AlreadyExists,
+                                       "too many temporary files exist"))
+                } else {
+                    retries += 1;
+                    continue;
+                }
+            },
+            res => res,
         }
     }
-    OsString::from_bytes(bytes).unwrap()
+}
+
+/// Swap the files at `a` and `b` with three renames through a temporary name next to `a`.
+///
+/// This is *not* atomic: in between, `a` doesn't exist. On failure, the original state is
+/// restored as far as possible.
+pub fn exchange_by_rename(a: &Path, b: &Path) -> io::Result<()> {
+    let dir = a.parent().unwrap_or(Path::new(""));
+    // Move `a` out of the way without clobbering anything that happens to live under the
+    // temporary name.
+    let a_is_dir = try!(fs::symlink_metadata(a)).is_dir();
+    let tmp = try!(create_helper(dir, &Builder::new(), |tmp| {
+        move_noclobber(a, &tmp, a_is_dir).map(|_| tmp)
+    }));
+    if let Err(e) = fs::rename(b, a) {
+        let _ = fs::rename(&tmp, a);
+        return Err(e);
+    }
+    if let Err(e) = fs::rename(&tmp, b) {
+        let _ = fs::rename(a, b);
+        let _ = fs::rename(&tmp, a);
+        return Err(e);
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn move_noclobber(from: &Path, to: &Path, is_dir: bool) -> io::Result<()> {
+    if !is_dir {
+        return ::imp::persist_noclobber(from, to);
+    }
+    // Directories can't be hard linked. Claim the name with an (exclusive) empty directory
+    // instead; renaming a directory over an empty one replaces it.
+    try!(fs::create_dir(to));
+    fs::rename(from, to).map_err(|e| {
+        let _ = fs::remove_dir(to);
+        e
+    })
+}
+
+#[cfg(windows)]
+fn move_noclobber(from: &Path, to: &Path, _is_dir: bool) -> io::Result<()> {
+    // MoveFileEx without MOVEFILE_REPLACE_EXISTING handles directories too.
+    ::imp::persist_noclobber(from, to)
 }
 
 // Stolen from std.
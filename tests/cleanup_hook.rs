@@ -0,0 +1,32 @@
+extern crate tempfile;
+use tempfile::NamedTempFile;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+// This lives in its own test binary since the hook affects the whole process.
+#[test]
+fn test_cleanup_error_hook() {
+    let failed: Arc<Mutex<Vec<PathBuf>>> = Arc::new(Mutex::new(Vec::new()));
+    let recorder = failed.clone();
+    tempfile::set_cleanup_error_hook(move |path, _| {
+        recorder.lock().unwrap().push(path.to_path_buf());
+    });
+
+    let tmpfile = NamedTempFile::new().unwrap();
+    let path = tmpfile.path().to_path_buf();
+    std::fs::remove_file(&path).unwrap();
+    drop(tmpfile);
+    assert_eq!(*failed.lock().unwrap(), vec![path]);
+
+    // Successful cleanups aren't reported.
+    drop(NamedTempFile::new().unwrap());
+    assert_eq!(failed.lock().unwrap().len(), 1);
+
+    // A panicking hook doesn't escape the destructor.
+    tempfile::set_cleanup_error_hook(|_, _| panic!("hook"));
+    let tmpfile = NamedTempFile::new().unwrap();
+    std::fs::remove_file(tmpfile.path()).unwrap();
+    drop(tmpfile);
+
+    tempfile::clear_cleanup_error_hook();
+}
@@ -0,0 +1,17 @@
+extern crate tempfile;
+use tempfile::{TempDir, NamedTempFile};
+use std::env;
+
+// This lives in its own test binary since the override affects the whole process.
+#[test]
+fn test_set_default_dir() {
+    let dir = TempDir::new().unwrap();
+    tempfile::set_default_dir(dir.path());
+    assert_eq!(tempfile::default_temp_dir(), dir.path());
+    let tmpfile = NamedTempFile::new().unwrap();
+    assert_eq!(tmpfile.dir(), Some(dir.path()));
+    drop(tmpfile);
+
+    tempfile::clear_default_dir();
+    assert_eq!(tempfile::default_temp_dir(), env::temp_dir());
+}
@@ -1,6 +1,6 @@
 #![feature(path_ext)]
 extern crate tempfile;
-use tempfile::NamedTempFile;
+use tempfile::{NamedTempFile, Builder};
 use std::env;
 use std::io::{Write, Read, Seek, SeekFrom};
 use std::fs::{PathExt, File};
@@ -61,3 +61,578 @@ fn test_persist() {
     }
     std::fs::remove_file(&persist_path).unwrap();
 }
+
+#[test]
+fn test_customnamed() {
+    let tmpfile = Builder::new().prefix("tmp").suffix(".rs").rand_bytes(12).tempfile().unwrap();
+    let name = tmpfile.path().file_name().unwrap().to_str().unwrap();
+    assert!(name.starts_with("tmp"));
+    assert!(name.ends_with(".rs"));
+    assert_eq!(name.len(), 18);
+}
+
+#[test]
+fn test_invalid_affix() {
+    let err = Builder::new().prefix("a/b").tempfile().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    let err = Builder::new().suffix("/c").tempfile().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_invalid_name() {
+    // These would name the directory itself (or its parent), which always exists.
+    let err = Builder::new().prefix("").suffix("").rand_bytes(0).tempfile().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    let err = Builder::new().prefix("").rand_bytes(1).rand_alphabet(b".").tempfile().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    let err = Builder::new().prefix("..").rand_bytes(0).make_name().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_as_file() {
+    let mut tmpfile = NamedTempFile::new().unwrap();
+    write!(tmpfile.as_file_mut(), "abcde").unwrap();
+    assert_eq!(tmpfile.as_file().metadata().unwrap().len(), 5);
+}
+
+#[test]
+fn test_rand_bytes() {
+    let tmpfile = Builder::new().prefix("").rand_bytes(20).tempfile().unwrap();
+    let name = tmpfile.path().file_name().unwrap().to_str().unwrap();
+    assert_eq!(name.len(), 20);
+    assert!(name.chars().all(|c| c.is_alphanumeric()));
+}
+
+#[test]
+fn test_keep() {
+    let mut tmpfile = NamedTempFile::new().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    let (mut f, path) = tmpfile.keep().unwrap();
+    assert!(path.exists());
+    f.seek(SeekFrom::Start(0)).unwrap();
+    let mut buf = String::new();
+    f.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+    drop(f);
+    assert!(path.exists());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_persist_noclobber() {
+    let mut tmpfile = NamedTempFile::new().unwrap();
+    let old_path = tmpfile.path().to_path_buf();
+    let persist_target = NamedTempFile::new().unwrap();
+    let persist_path = persist_target.path().to_path_buf();
+    write!(tmpfile, "abcde").unwrap();
+    assert!(old_path.exists());
+    {
+        tmpfile = tmpfile.persist_noclobber(&persist_path).unwrap_err().file;
+        assert!(old_path.exists());
+        std::fs::remove_file(&persist_path).unwrap();
+        drop(persist_target);
+    }
+    tmpfile.persist_noclobber(&persist_path).unwrap();
+    // Try opening it at the new path.
+    let mut f = File::open(&persist_path).unwrap();
+    let mut buf = String::new();
+    f.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+    std::fs::remove_file(&persist_path).unwrap();
+}
+
+#[test]
+fn test_with_suffix() {
+    let tmpfile = NamedTempFile::with_suffix(".json").unwrap();
+    assert_eq!(tmpfile.path().extension().unwrap(), "json");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_mode() {
+    use std::os::unix::fs::PermissionsExt;
+    let tmpfile = Builder::new().mode(0o600).tempfile().unwrap();
+    assert_eq!(tmpfile.metadata().unwrap().permissions().mode() & 0o777, 0o600);
+    let tmpfile = Builder::new().mode(0o400).tempfile().unwrap();
+    assert_eq!(tmpfile.metadata().unwrap().permissions().mode() & 0o777, 0o400);
+}
+
+#[test]
+fn test_reopen() {
+    let mut tmpfile = NamedTempFile::new().unwrap();
+    let path = tmpfile.path().to_path_buf();
+    write!(tmpfile, "abcde").unwrap();
+    let mut reopened = tmpfile.reopen().unwrap();
+    let mut buf = String::new();
+    reopened.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+    drop(tmpfile);
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_from_parts() {
+    let path = env::temp_dir().join("from_parts_temporary_file");
+    let file = File::create(&path).unwrap();
+    let tmpfile = NamedTempFile::from_parts(file, path.clone());
+    assert_eq!(tmpfile.path(), &*path);
+    drop(tmpfile);
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_persist_error_path() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let bad_path = env::temp_dir().join("nonexistent_temporary_dir").join("file");
+    let err = tmpfile.persist(&bad_path).unwrap_err();
+    assert_eq!(err.path, bad_path);
+    assert!(err.file.path().exists());
+}
+
+#[test]
+fn test_persist_with_copy_fallback() {
+    let mut tmpfile = NamedTempFile::new().unwrap();
+    let old_path = tmpfile.path().to_path_buf();
+    let persist_path = env::temp_dir().join("persisted_copy_temporary_file");
+    write!(tmpfile, "abcde").unwrap();
+    tmpfile.persist_with_copy_fallback(&persist_path).unwrap();
+    assert!(!old_path.exists());
+    let mut f = File::open(&persist_path).unwrap();
+    let mut buf = String::new();
+    f.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+    std::fs::remove_file(&persist_path).unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_persist_with_copy_fallback_across_devices() {
+    use std::os::unix::fs::MetadataExt;
+    // Only exercises the copy path if /dev/shm and the temporary directory are on different
+    // filesystems (the usual setup).
+    let dir = match tempfile::TempDir::new_in("/dev/shm") {
+        Ok(dir) => dir,
+        Err(_) => return,
+    };
+    let mut tmpfile = NamedTempFile::new().unwrap();
+    let dev = |p: &std::path::Path| std::fs::metadata(p).unwrap().dev();
+    if dev(dir.path()) == dev(tmpfile.path()) {
+        return;
+    }
+    let old_path = tmpfile.path().to_path_buf();
+    write!(tmpfile, "abcde").unwrap();
+    let persist_path = dir.path().join("target");
+    tmpfile.persist_with_copy_fallback(&persist_path).unwrap();
+    assert!(!old_path.exists());
+    let mut buf = String::new();
+    File::open(&persist_path).unwrap().read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+}
+
+#[test]
+fn test_into_file() {
+    let mut tmpfile = NamedTempFile::new().unwrap();
+    let path = tmpfile.path().to_path_buf();
+    write!(tmpfile, "abcde").unwrap();
+    let mut f = tmpfile.into_file();
+    f.seek(SeekFrom::Start(0)).unwrap();
+    let mut buf = String::new();
+    f.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+    drop(f);
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_dir() {
+    let dir = env::temp_dir();
+    let tmpfile = NamedTempFile::new_in(&dir).unwrap();
+    assert_eq!(tmpfile.dir(), Some(&*dir));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_dir_without_parent() {
+    let file = NamedTempFile::new().unwrap().into_file();
+    let tmpfile = NamedTempFile::from_parts(file, std::path::PathBuf::from("/"));
+    assert_eq!(tmpfile.dir(), None);
+    // Don't try to delete "/".
+    tmpfile.keep().unwrap();
+}
+
+#[test]
+fn test_max_retries() {
+    let dir = tempfile::TempDir::new().unwrap();
+    // Without any randomness, every attempt collides with the first file.
+    let mut builder = Builder::new();
+    builder.prefix("collide").rand_bytes(0).max_retries(3);
+    let _first = builder.tempfile_in(dir.path()).unwrap();
+    let err = builder.tempfile_in(dir.path()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+}
+
+#[test]
+fn test_make_name() {
+    let name = Builder::new().prefix("lock-").suffix(".lck").make_name().unwrap();
+    let name = name.to_str().unwrap();
+    assert!(name.starts_with("lock-"));
+    assert!(name.ends_with(".lck"));
+    assert!(name[5..name.len() - 4].chars().all(|c| c.is_alphanumeric()));
+}
+
+#[test]
+fn test_rng_seed() {
+    let mut a = Builder::new();
+    a.rng_seed_for_testing(42);
+    let mut b = Builder::new();
+    b.rng_seed_for_testing(42);
+    let names: Vec<_> = (0..3).map(|_| a.make_name().unwrap()).collect();
+    assert!(names[0] != names[1] && names[1] != names[2]);
+    for name in &names {
+        assert_eq!(*name, b.make_name().unwrap());
+    }
+}
+
+#[test]
+fn test_builder_sync() {
+    fn assert_sync<T: Sync + Send>(_: &T) {}
+    let mut builder = Builder::new();
+    builder.rng_seed_for_testing(42);
+    assert_sync(&builder);
+}
+
+#[test]
+fn test_close_error() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let path = tmpfile.path().to_path_buf();
+    std::fs::remove_file(&path).unwrap();
+    let err = tmpfile.close().unwrap_err();
+    assert_eq!(err.path, path);
+    assert_eq!(err.error.kind(), std::io::ErrorKind::NotFound);
+}
+
+#[test]
+fn test_persist_durable() {
+    let mut tmpfile = NamedTempFile::new().unwrap();
+    let persist_path = env::temp_dir().join("persisted_durable_temporary_file");
+    write!(tmpfile, "abcde").unwrap();
+    tmpfile.persist_durable(&persist_path).unwrap();
+    let mut f = File::open(&persist_path).unwrap();
+    let mut buf = String::new();
+    f.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+    std::fs::remove_file(&persist_path).unwrap();
+}
+
+#[test]
+fn test_with_prefix_suffix() {
+    let tmpfile = NamedTempFile::with_prefix_suffix_in(env::temp_dir(), "data-", ".wasm").unwrap();
+    let name = tmpfile.path().file_name().unwrap().to_str().unwrap();
+    assert!(name.starts_with("data-"));
+    assert!(name.ends_with(".wasm"));
+    assert!(NamedTempFile::with_prefix_suffix("a/b", "").is_err());
+}
+
+#[test]
+fn test_exists_on_disk() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    assert!(tmpfile.exists_on_disk());
+    // On Windows, the path can't be reused until the file is closed.
+    if cfg!(unix) {
+        std::fs::remove_file(tmpfile.path()).unwrap();
+        assert!(!tmpfile.exists_on_disk());
+        // Replace it with a different file.
+        File::create(tmpfile.path()).unwrap();
+        assert!(!tmpfile.exists_on_disk());
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn test_persist_with_mode() {
+    use std::os::unix::fs::PermissionsExt;
+    let tmpfile = NamedTempFile::new().unwrap();
+    let persist_path = env::temp_dir().join("persisted_mode_temporary_file");
+    let f = tmpfile.persist_with_mode(&persist_path, 0o644).unwrap();
+    assert_eq!(f.metadata().unwrap().permissions().mode() & 0o777, 0o644);
+    std::fs::remove_file(&persist_path).unwrap();
+}
+
+#[test]
+fn test_rename_to() {
+    use std::ffi::OsStr;
+    let dir = tempfile::TempDir::new().unwrap();
+    let mut tmpfile = NamedTempFile::new_in(dir.path()).unwrap();
+    let old_path = tmpfile.path().to_path_buf();
+    tmpfile.rename_to(OsStr::new("report.txt")).unwrap();
+    assert!(!old_path.exists());
+    assert_eq!(tmpfile.path(), &*dir.path().join("report.txt"));
+    assert!(tmpfile.path().exists());
+    assert!(tmpfile.rename_to(OsStr::new("../escape")).is_err());
+    let path = tmpfile.path().to_path_buf();
+    drop(tmpfile);
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_batch_in() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let files = NamedTempFile::batch_in(dir.path(), "batch-", 5).unwrap();
+    assert_eq!(files.len(), 5);
+    for f in &files {
+        assert!(f.path().file_name().unwrap().to_str().unwrap().starts_with("batch-"));
+    }
+    drop(files);
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+    // A bad prefix fails without leaving anything behind.
+    assert!(NamedTempFile::batch_in(dir.path(), "a/b", 3).is_err());
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_into_temp_path() {
+    let mut tmpfile = NamedTempFile::new().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    let path = tmpfile.into_temp_path();
+    let mut buf = String::new();
+    File::open(&path).unwrap().read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+    let pathbuf = path.to_path_buf();
+    drop(path);
+    assert!(!pathbuf.exists());
+
+    let path = NamedTempFile::new().unwrap().into_temp_path();
+    let pathbuf = path.keep();
+    assert!(pathbuf.exists());
+    std::fs::remove_file(pathbuf).unwrap();
+}
+
+#[test]
+fn test_hash_eq() {
+    use std::collections::HashSet;
+    let a = NamedTempFile::new().unwrap();
+    let b = NamedTempFile::new().unwrap();
+    assert!(a == a);
+    assert!(a != b);
+    let path = a.path().to_path_buf();
+    let mut set = HashSet::new();
+    set.insert(a);
+    set.insert(b);
+    assert_eq!(set.len(), 2);
+    assert!(set.iter().any(|f| f.path() == &*path));
+}
+
+#[test]
+fn test_rewind_for_read() {
+    let mut tmpfile = NamedTempFile::new().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    tmpfile.rewind_for_read().unwrap();
+    let mut buf = String::new();
+    tmpfile.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+}
+
+#[test]
+fn test_reserve_in() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = NamedTempFile::reserve_in(dir.path()).unwrap();
+    assert!(path.exists());
+    assert_eq!(path.metadata().unwrap().len(), 0);
+    let pathbuf = path.to_path_buf();
+    drop(path);
+    assert!(!pathbuf.exists());
+}
+
+#[test]
+fn test_verify_same_file() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let other = File::open(tmpfile.path()).unwrap();
+    assert!(tmpfile.verify_same_file(&other).unwrap());
+    let unrelated = NamedTempFile::new().unwrap();
+    assert!(!tmpfile.verify_same_file(unrelated.as_file()).unwrap());
+}
+
+#[test]
+fn test_truncate_to_at_most() {
+    let mut tmpfile = NamedTempFile::new().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    assert!(!tmpfile.truncate_to_at_most(5).unwrap());
+    assert!(tmpfile.truncate_to_at_most(2).unwrap());
+    assert_eq!(tmpfile.metadata().unwrap().len(), 2);
+}
+
+#[test]
+fn test_persist_with() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let target = dir.path().join("published");
+    tempfile::persist_with(dir.path(), &target, |f| write!(f, "abcde")).unwrap();
+    let mut buf = String::new();
+    File::open(&target).unwrap().read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+
+    let err = tempfile::persist_with(dir.path(), &target, |f| {
+        try!(write!(f, "partial"));
+        Err(std::io::Error::new(std::io::ErrorKind::Other, "failed"))
+    }).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::Other);
+    let mut buf = String::new();
+    File::open(&target).unwrap().read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_persist_into_dir() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let mut tmpfile = NamedTempFile::new().unwrap();
+    let name = tmpfile.path().file_name().unwrap().to_os_string();
+    write!(tmpfile, "abcde").unwrap();
+    tmpfile.persist(dir.path()).unwrap();
+    let mut buf = String::new();
+    File::open(dir.path().join(name)).unwrap().read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+}
+
+#[test]
+fn test_new_beside() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let target = dir.path().join("target.txt");
+    let tmpfile = NamedTempFile::new_beside(&target).unwrap();
+    assert_eq!(tmpfile.dir(), Some(dir.path()));
+    tmpfile.persist(&target).unwrap();
+    assert!(target.exists());
+}
+
+#[test]
+fn test_exchange_paths() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let a = dir.path().join("a");
+    let b = dir.path().join("b");
+    write!(File::create(&a).unwrap(), "live").unwrap();
+    write!(File::create(&b).unwrap(), "standby").unwrap();
+    tempfile::exchange_paths(&a, &b).unwrap();
+    let mut buf = String::new();
+    File::open(&a).unwrap().read_to_string(&mut buf).unwrap();
+    assert_eq!("standby", buf);
+    buf.clear();
+    File::open(&b).unwrap().read_to_string(&mut buf).unwrap();
+    assert_eq!("live", buf);
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_exchange_paths_nested() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let a = dir.path().join("a");
+    let b = a.join("b");
+    std::fs::create_dir(&a).unwrap();
+    File::create(&b).unwrap();
+    // Swapping a directory with one of its children is an error, not a reason to fall back on
+    // renames.
+    assert!(tempfile::exchange_paths(&a, &b).is_err());
+    assert!(a.is_dir());
+    assert!(b.is_file());
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_copy_security_context_from() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let tmpfile = NamedTempFile::new().unwrap();
+    // A no-op without SELinux; otherwise, relabeling like a directory we own is allowed.
+    tmpfile.copy_security_context_from(dir.path()).unwrap();
+}
+
+#[test]
+fn test_rand_alphabet() {
+    let mut builder = Builder::new();
+    builder.prefix("").rand_bytes(32).rand_alphabet(b"abc");
+    for _ in 0..10 {
+        let name = builder.make_name().unwrap();
+        assert!(name.to_str().unwrap().chars().all(|c| c == 'a' || c == 'b' || c == 'c'));
+    }
+    let tmpfile = builder.tempfile().unwrap();
+    let name = tmpfile.path().file_name().unwrap().to_str().unwrap();
+    assert!(name.chars().all(|c| c == 'a' || c == 'b' || c == 'c'));
+
+    let err = Builder::new().rand_alphabet(b"a/b").tempfile().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(Builder::new().rand_alphabet(b"").make_name().is_err());
+}
+
+#[test]
+fn test_retry_timeout() {
+    use std::time::Duration;
+    let dir = tempfile::TempDir::new().unwrap();
+    let mut builder = Builder::new();
+    builder.prefix("collide").rand_bytes(0).retry_timeout(Duration::from_millis(10));
+    let _first = builder.tempfile_in(dir.path()).unwrap();
+    let err = builder.tempfile_in(dir.path()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+}
+
+#[test]
+fn test_with_contents() {
+    let mut tmpfile = NamedTempFile::with_contents_in(env::temp_dir(), b"abcde").unwrap();
+    let mut buf = String::new();
+    tmpfile.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+}
+
+#[test]
+fn test_set_permissions() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let mut perm = tmpfile.metadata().unwrap().permissions();
+    perm.set_readonly(true);
+    tmpfile.set_permissions(perm).unwrap();
+    assert!(tmpfile.metadata().unwrap().permissions().readonly());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tmpfile.set_permissions(std::fs::Permissions::from_mode(0o644)).unwrap();
+        assert_eq!(tmpfile.metadata().unwrap().permissions().mode() & 0o777, 0o644);
+    }
+    #[cfg(windows)]
+    {
+        // Read-only files can't be deleted on Windows.
+        let mut perm = tmpfile.metadata().unwrap().permissions();
+        perm.set_readonly(false);
+        tmpfile.set_permissions(perm).unwrap();
+    }
+}
+
+#[test]
+fn test_flush_sync() {
+    let mut tmpfile = NamedTempFile::new().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    tmpfile.flush_sync().unwrap();
+    assert_eq!(tmpfile.metadata().unwrap().len(), 5);
+}
+
+#[test]
+fn test_counter() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let mut builder = Builder::new();
+    builder.prefix("test-").counter(1);
+    // Collides with the first name so the counter is bumped past it.
+    File::create(dir.path().join("test-0001")).unwrap();
+    let a = builder.tempfile_in(dir.path()).unwrap();
+    let b = builder.clone().tempfile_in(dir.path()).unwrap();
+    assert_eq!(a.path().file_name().unwrap(), "test-0002");
+    assert_eq!(b.path().file_name().unwrap(), "test-0003");
+}
+
+#[test]
+fn test_find_leftovers() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let leftover = Builder::new().prefix("job-").tempfile_in(dir.path()).unwrap().into_path();
+    let _suffixed = Builder::new().prefix("job-").suffix(".txt").tempfile_in(dir.path()).unwrap();
+    let _other = Builder::new().prefix("other-").tempfile_in(dir.path()).unwrap();
+    std::fs::create_dir(dir.path().join("job-abcdef")).unwrap();
+    assert_eq!(tempfile::find_leftovers(dir.path(), "job-").unwrap(), vec![leftover]);
+}
@@ -0,0 +1,48 @@
+extern crate tempfile;
+use tempfile::SpooledTempFile;
+use std::io::{Write, Read, Seek, SeekFrom};
+
+#[test]
+fn test_in_memory() {
+    let mut tmpfile = SpooledTempFile::new(10);
+    write!(tmpfile, "abcde").unwrap();
+    assert!(!tmpfile.is_rolled());
+    tmpfile.seek(SeekFrom::Start(0)).unwrap();
+    let mut buf = String::new();
+    tmpfile.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+}
+
+#[test]
+fn test_rollover_on_write() {
+    let mut tmpfile = SpooledTempFile::new(10);
+    write!(tmpfile, "abcde").unwrap();
+    write!(tmpfile, "fghijk").unwrap();
+    assert!(tmpfile.is_rolled());
+    tmpfile.seek(SeekFrom::Start(0)).unwrap();
+    let mut buf = String::new();
+    tmpfile.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcdefghijk", buf);
+}
+
+#[test]
+fn test_rollover_on_seek() {
+    let mut tmpfile = SpooledTempFile::new(10);
+    write!(tmpfile, "abcde").unwrap();
+    assert_eq!(tmpfile.seek(SeekFrom::Start(20)).unwrap(), 20);
+    assert!(tmpfile.is_rolled());
+    assert_eq!(tmpfile.seek(SeekFrom::Current(0)).unwrap(), 20);
+}
+
+#[test]
+fn test_explicit_roll() {
+    let mut tmpfile = SpooledTempFile::new(10);
+    write!(tmpfile, "abcde").unwrap();
+    tmpfile.roll().unwrap();
+    assert!(tmpfile.is_rolled());
+    assert_eq!(tmpfile.seek(SeekFrom::Current(0)).unwrap(), 5);
+    tmpfile.seek(SeekFrom::Start(0)).unwrap();
+    let mut buf = String::new();
+    tmpfile.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+}
@@ -0,0 +1,52 @@
+#![feature(path_ext)]
+extern crate tempfile;
+use tempfile::TempDir;
+use std::fs::{self, PathExt, File};
+use std::io::Write;
+
+#[test]
+fn test_deleted() {
+    let tmpdir = TempDir::new().unwrap();
+    let path = tmpdir.path().to_path_buf();
+    assert!(path.is_dir());
+    {
+        let mut f = File::create(path.join("file")).unwrap();
+        write!(f, "abcde").unwrap();
+    }
+    fs::create_dir(path.join("subdir")).unwrap();
+    drop(tmpdir);
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_close() {
+    let tmpdir = TempDir::new().unwrap();
+    let path = tmpdir.path().to_path_buf();
+    tmpdir.close().unwrap();
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_into_path() {
+    let tmpdir = TempDir::new().unwrap();
+    let path = tmpdir.into_path();
+    assert!(path.is_dir());
+    fs::remove_dir_all(path).unwrap();
+}
+
+#[test]
+fn test_builder() {
+    let tmpdir = tempfile::Builder::new().prefix("dir-").rand_bytes(16).tempdir().unwrap();
+    let name = tmpdir.path().file_name().unwrap().to_str().unwrap().to_string();
+    assert!(name.starts_with("dir-"));
+    assert_eq!(name.len(), 20);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+    let tmpdir = TempDir::new().unwrap();
+    let mode = fs::metadata(tmpdir.path()).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o700);
+}
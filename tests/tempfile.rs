@@ -1,6 +1,6 @@
 extern crate tempfile;
 use tempfile::TempFile;
-use std::io::{Write, Read, Seek, SeekFrom};
+use std::io::{Write, Read, Seek, SeekFrom, IoSlice, IoSliceMut};
 
 #[test]
 fn test_basic() {
@@ -31,3 +31,691 @@ fn test_shared() {
     tmpfiles[1].read_to_string(&mut buf).unwrap();
     assert_eq!("abcde", buf);
 }
+
+#[test]
+fn test_as_file() {
+    let mut tmpfile = TempFile::new().unwrap();
+    write!(tmpfile.as_file_mut(), "abcde").unwrap();
+    assert_eq!(tmpfile.as_file().metadata().unwrap().len(), 5);
+}
+
+#[test]
+fn test_try_clone() {
+    let mut tmpfile = TempFile::new().unwrap();
+    let mut clone = tmpfile.try_clone().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    // The offset is shared so the clone is at the end of the file too.
+    assert_eq!(clone.seek(SeekFrom::Current(0)).unwrap(), 5);
+    clone.seek(SeekFrom::Start(0)).unwrap();
+    let mut buf = String::new();
+    tmpfile.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+}
+
+#[test]
+fn test_is_empty() {
+    let mut tmpfile = TempFile::new().unwrap();
+    assert!(tmpfile.is_empty().unwrap());
+    write!(tmpfile, "abcde").unwrap();
+    assert!(!tmpfile.is_empty().unwrap());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_shared_independent() {
+    use std::os::unix::io::AsRawFd;
+    use std::os::unix::fs::MetadataExt;
+
+    let mut tmpfiles = TempFile::shared(3).unwrap();
+    write!(tmpfiles[0], "abcde").unwrap();
+
+    let fds: Vec<_> = tmpfiles.iter().map(|f| f.as_raw_fd()).collect();
+    assert!(fds[0] != fds[1] && fds[1] != fds[2] && fds[0] != fds[2]);
+
+    let metas: Vec<_> = tmpfiles.iter().map(|f| f.as_file().metadata().unwrap()).collect();
+    for meta in &metas[1..] {
+        assert_eq!(meta.dev(), metas[0].dev());
+        assert_eq!(meta.ino(), metas[0].ino());
+    }
+
+    // Offsets are independent.
+    assert_eq!(tmpfiles[0].seek(SeekFrom::Current(0)).unwrap(), 5);
+    assert_eq!(tmpfiles[1].seek(SeekFrom::Current(0)).unwrap(), 0);
+    assert_eq!(tmpfiles[2].seek(SeekFrom::Current(0)).unwrap(), 0);
+}
+
+#[test]
+fn test_read_all() {
+    let mut tmpfile = TempFile::new().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    tmpfile.seek(SeekFrom::Start(2)).unwrap();
+    assert_eq!(tmpfile.read_all().unwrap(), b"abcde");
+    assert_eq!(tmpfile.seek(SeekFrom::Current(0)).unwrap(), 2);
+}
+
+#[test]
+fn test_debug() {
+    let tmpfile = TempFile::new().unwrap();
+    assert!(format!("{:?}", tmpfile).starts_with("TempFile {"));
+}
+
+#[test]
+fn test_into_read_only() {
+    let mut tmpfile = TempFile::new().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    let mut ro = tmpfile.into_read_only().unwrap();
+    let mut buf = String::new();
+    ro.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+    assert_eq!(ro.len().unwrap(), 5);
+    assert!(ro.metadata().unwrap().is_file());
+}
+
+#[test]
+fn test_unnamed_max_retries() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::File::create(dir.path().join("collide")).unwrap();
+    let mut builder = tempfile::Builder::new();
+    builder.prefix("collide").rand_bytes(0).max_retries(3);
+    // O_TMPFILE doesn't pick a name at all.
+    #[cfg(target_os = "linux")]
+    builder.use_o_tmpfile(false);
+    let err = builder.unnamed_in(dir.path()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_no_o_tmpfile() {
+    let mut tmpfile = tempfile::Builder::new().use_o_tmpfile(false).unnamed().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    assert_eq!(tmpfile.read_all().unwrap(), b"abcde");
+    let mut shared = tmpfile.reopen().unwrap();
+    let mut buf = String::new();
+    shared.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+}
+
+#[test]
+fn test_positional() {
+    let tmpfile = TempFile::new().unwrap();
+    assert_eq!(tmpfile.write_at(b"abcde", 3).unwrap(), 5);
+    let mut buf = [0u8; 3];
+    assert_eq!(tmpfile.read_at(&mut buf, 4).unwrap(), 3);
+    assert_eq!(&buf, b"bcd");
+    // Only *nix leaves the offset alone.
+    if cfg!(unix) {
+        let mut tmpfile = tmpfile;
+        assert_eq!(tmpfile.seek(SeekFrom::Current(0)).unwrap(), 0);
+    }
+}
+
+#[test]
+fn test_dir() {
+    let dir = std::env::temp_dir();
+    let tmpfile = TempFile::new_in(&dir).unwrap();
+    assert_eq!(tmpfile.dir(), Some(&*dir));
+    assert_eq!(tmpfile.try_clone().unwrap().dir(), Some(&*dir));
+}
+
+#[test]
+fn test_position() {
+    let mut tmpfile = TempFile::new().unwrap();
+    assert_eq!(tmpfile.position().unwrap(), 0);
+    write!(tmpfile, "abcde").unwrap();
+    assert_eq!(tmpfile.position().unwrap(), 5);
+    assert_eq!(tmpfile.position().unwrap(), 5);
+}
+
+#[test]
+fn test_vectored() {
+    let mut tmpfile = TempFile::new().unwrap();
+    let n = tmpfile.write_vectored(&[IoSlice::new(b"ab"), IoSlice::new(b"cde")]).unwrap();
+    tmpfile.seek(SeekFrom::Start(0)).unwrap();
+    let (mut a, mut b) = ([0u8; 2], [0u8; 3]);
+    let m = tmpfile.read_vectored(&mut [IoSliceMut::new(&mut a), IoSliceMut::new(&mut b)]).unwrap();
+    assert_eq!(n, m);
+    assert_eq!(&a[..], &b"abcde"[..2]);
+    assert_eq!(&b[..n - 2], &b"cde"[..n - 2]);
+}
+
+#[test]
+fn test_shared_dup() {
+    let mut tmpfiles = TempFile::shared_dup(2).unwrap();
+    write!(tmpfiles[0], "abcde").unwrap();
+    assert_eq!(tmpfiles[1].position().unwrap(), 5);
+    tmpfiles[1].seek(SeekFrom::Start(1)).unwrap();
+    let mut buf = String::new();
+    tmpfiles[0].read_to_string(&mut buf).unwrap();
+    assert_eq!("bcde", buf);
+}
+
+#[test]
+fn test_append() {
+    let mut tmpfile = tempfile::Builder::new().append(true).unnamed().unwrap();
+    write!(tmpfile, "abc").unwrap();
+    tmpfile.seek(SeekFrom::Start(0)).unwrap();
+    write!(tmpfile, "de").unwrap();
+    assert_eq!(tmpfile.read_all().unwrap(), b"abcde");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_secure_zeroed() {
+    let mut tmpfile = tempfile::SecureTempFile::new().unwrap();
+    write!(tmpfile, "secret").unwrap();
+    // Keep an independent handle on the same file to observe the wipe.
+    let mut observer = std::fs::File::open(format!("/proc/self/fd/{}", {
+        use std::os::unix::io::AsRawFd;
+        tmpfile.as_file().as_raw_fd()
+    })).unwrap();
+    drop(tmpfile);
+    let mut buf = Vec::new();
+    observer.read_to_end(&mut buf).unwrap();
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn test_secure_zero() {
+    let mut tmpfile = tempfile::SecureTempFile::new().unwrap();
+    write!(tmpfile, "secret").unwrap();
+    tmpfile.zero().unwrap();
+    assert_eq!(tmpfile.len().unwrap(), 6);
+    assert_eq!(tmpfile.seek(SeekFrom::Current(0)).unwrap(), 6);
+    let mut buf = Vec::new();
+    tmpfile.seek(SeekFrom::Start(0)).unwrap();
+    tmpfile.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, [0u8; 6]);
+}
+
+#[test]
+fn test_copy_to() {
+    let mut tmpfile = TempFile::new().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    let mut out = Vec::new();
+    assert_eq!(tmpfile.copy_to(&mut out).unwrap(), 5);
+    assert_eq!(out, b"abcde");
+    assert_eq!(tmpfile.position().unwrap(), 5);
+}
+
+#[test]
+fn test_new_sized() {
+    let tmpfile = TempFile::new_sized(4096).unwrap();
+    assert_eq!(tmpfile.len().unwrap(), 4096);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_new_sized_reserved() {
+    let tmpfile = TempFile::new_sized_reserved(4096).unwrap();
+    assert_eq!(tmpfile.len().unwrap(), 4096);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_new_sized_reserved_too_large() {
+    let err = TempFile::new_sized_reserved(i64::max_value() as u64 + 1).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    let err = TempFile::new_sized_reserved(u64::max_value()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_try_from() {
+    use std::convert::TryFrom;
+    let file = TempFile::new().unwrap().as_file().try_clone().unwrap();
+    let mut tmpfile = TempFile::try_from(file).unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    assert_eq!(tmpfile.len().unwrap(), 5);
+
+    // Windows can't open directories as files.
+    if cfg!(unix) {
+        let dir = std::fs::File::open(std::env::temp_dir()).unwrap();
+        assert!(TempFile::try_from(dir).is_err());
+    }
+}
+
+#[test]
+fn test_metadata() {
+    let mut tmpfile = TempFile::new().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    let meta = tmpfile.metadata().unwrap();
+    assert!(meta.is_file());
+    assert_eq!(meta.len(), 5);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_stdio() {
+    use std::process::{Command, Stdio};
+    let mut tmpfile = TempFile::new().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    tmpfile.seek(SeekFrom::Start(0)).unwrap();
+    let output = Command::new("cat").stdin(Stdio::from(tmpfile)).output().unwrap();
+    assert_eq!(output.stdout, b"abcde");
+}
+
+#[test]
+fn test_iter() {
+    let mut tmpfiles: Vec<TempFile> = TempFile::iter().take(3).map(|f| f.unwrap()).collect();
+    write!(tmpfiles[0], "abcde").unwrap();
+    assert!(tmpfiles[1].is_empty().unwrap());
+    assert!(tmpfiles[2].is_empty().unwrap());
+
+    let mut iter = TempFile::iter_in("/nonexistent/temporary/dir");
+    assert!(iter.next().unwrap().is_err());
+    assert!(iter.next().is_none());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_cloexec() {
+    use std::os::unix::io::AsRawFd;
+    use std::process::Command;
+
+    fn inherited(tmpfile: &TempFile) -> bool {
+        Command::new("sh").arg("-c").arg(format!("test -e /dev/fd/{}", tmpfile.as_raw_fd()))
+            .status().unwrap().success()
+    }
+
+    let tmpfile = TempFile::new().unwrap();
+    assert!(!inherited(&tmpfile));
+    let tmpfile = tempfile::Builder::new().cloexec(false).unnamed().unwrap();
+    assert!(inherited(&tmpfile));
+}
+
+#[test]
+fn test_shared_ref() {
+    let tmpfile = TempFile::new().unwrap();
+    write!(&tmpfile, "abcde").unwrap();
+    (&tmpfile).seek(SeekFrom::Start(0)).unwrap();
+    let mut buf = String::new();
+    (&tmpfile).read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_into_raw_fd() {
+    use std::os::unix::io::{IntoRawFd, FromRawFd};
+    let mut tmpfile = TempFile::new().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    let fd = tmpfile.into_raw_fd();
+    let mut f = unsafe { std::fs::File::from_raw_fd(fd) };
+    f.seek(SeekFrom::Start(0)).unwrap();
+    let mut buf = String::new();
+    f.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+}
+
+#[test]
+fn test_new_in_checked() {
+    use std::io::ErrorKind;
+    assert!(TempFile::new_in_checked(std::env::temp_dir()).is_ok());
+    let err = TempFile::new_in_checked("/nonexistent/temporary/dir").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let err = TempFile::new_in_checked(file.path()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotADirectory);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_memfd() {
+    let mut tmpfile = TempFile::new_memfd("test").unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    assert_eq!(tmpfile.read_all().unwrap(), b"abcde");
+    tmpfile.set_len(1 << 20).unwrap();
+    assert_eq!(tmpfile.len().unwrap(), 1 << 20);
+    assert_eq!(tmpfile.dir(), None);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_seals() {
+    use tempfile::SealFlags;
+    let mut tmpfile = TempFile::new_memfd("sealed").unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    assert_eq!(tmpfile.get_seals().unwrap(), SealFlags::EMPTY);
+    tmpfile.add_seals(SealFlags::WRITE | SealFlags::SHRINK | SealFlags::GROW).unwrap();
+    assert!(tmpfile.get_seals().unwrap().contains(SealFlags::WRITE));
+    assert!(write!(tmpfile, "fgh").is_err());
+    assert!(tmpfile.set_len(0).is_err());
+    assert_eq!(tmpfile.read_all().unwrap(), b"abcde");
+
+    assert!(TempFile::new().unwrap().add_seals(SealFlags::WRITE).is_err());
+}
+
+#[test]
+fn test_lazy() {
+    #[derive(Default)]
+    struct Scratch {
+        file: tempfile::LazyTempFile,
+    }
+
+    let mut scratch = Scratch::default();
+    assert!(!scratch.file.is_created());
+    write!(scratch.file, "abcde").unwrap();
+    assert!(scratch.file.is_created());
+    let mut tmpfile = scratch.file.into_inner().unwrap();
+    assert_eq!(tmpfile.read_all().unwrap(), b"abcde");
+}
+
+#[test]
+fn test_rewind_for_read() {
+    let mut tmpfile = TempFile::new().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    tmpfile.rewind_for_read().unwrap();
+    let mut buf = String::new();
+    tmpfile.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+}
+
+#[test]
+fn test_with_options() {
+    use std::fs::OpenOptions;
+    let dir = tempfile::TempDir::new().unwrap();
+    let mut opts = OpenOptions::new();
+    opts.read(true).append(true);
+    let mut tmpfile = TempFile::with_options(&opts, dir.path()).unwrap();
+    write!(tmpfile, "abc").unwrap();
+    tmpfile.seek(SeekFrom::Start(0)).unwrap();
+    write!(tmpfile, "de").unwrap();
+    assert_eq!(tmpfile.read_all().unwrap(), b"abcde");
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+    let mut opts = OpenOptions::new();
+    opts.read(true);
+    let err = TempFile::with_options(&opts, dir.path()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_read_to_string_sized() {
+    let mut tmpfile = TempFile::new().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    assert_eq!(tmpfile.read_to_string_sized().unwrap(), "abcde");
+    tmpfile.set_len(2).unwrap();
+    assert_eq!(tmpfile.read_to_string_sized().unwrap(), "ab");
+}
+
+#[test]
+fn test_truncate_to_at_most() {
+    let mut tmpfile = TempFile::new().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    assert!(!tmpfile.truncate_to_at_most(10).unwrap());
+    assert_eq!(tmpfile.len().unwrap(), 5);
+    assert!(tmpfile.truncate_to_at_most(3).unwrap());
+    assert_eq!(tmpfile.len().unwrap(), 3);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_unlink_now() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let tmpfile = tempfile::Builder::new().unlink_now(false).unnamed_in(dir.path()).unwrap();
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    drop(tmpfile);
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+    let _tmpfile = tempfile::Builder::new().unnamed_in(dir.path()).unwrap();
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_shared_pair() {
+    let (mut writer, mut reader) = TempFile::shared_pair().unwrap();
+    write!(writer, "abcde").unwrap();
+    let mut buf = String::new();
+    reader.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+}
+
+#[test]
+fn test_blksize() {
+    let tmpfile = TempFile::new().unwrap();
+    let blksize = tmpfile.blksize().unwrap();
+    assert!(blksize > 0);
+}
+
+#[test]
+#[cfg(windows)]
+fn test_delete_on_close() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let tmpfile = tempfile::Builder::new().delete_on_close(false).unnamed_in(dir.path()).unwrap();
+    let path = std::fs::read_dir(dir.path()).unwrap().next().unwrap().unwrap().path();
+    std::fs::File::open(&path).unwrap();
+    drop(tmpfile);
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_shared_best_effort() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let (mut tmpfiles, err) = TempFile::shared_best_effort(dir.path(), 3);
+    assert!(err.is_none());
+    assert_eq!(tmpfiles.len(), 3);
+    // Unlike a NamedTempFile, no name is left behind.
+    #[cfg(unix)]
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    write!(tmpfiles[0], "abcde").unwrap();
+    let mut buf = String::new();
+    tmpfiles[2].read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+
+    let (tmpfiles, err) = TempFile::shared_best_effort(dir.path().join("missing"), 3);
+    assert!(tmpfiles.is_empty());
+    assert!(err.is_some());
+}
+
+#[test]
+fn test_buf_reader() {
+    use std::io::BufRead;
+    let mut tmpfile = TempFile::new().unwrap();
+    write!(tmpfile, "ab\ncd\n").unwrap();
+    tmpfile.rewind_for_read().unwrap();
+    let lines: Vec<String> = tmpfile.buf_reader().lines().map(|l| l.unwrap()).collect();
+    assert_eq!(lines, vec!["ab", "cd"]);
+    tmpfile.rewind_for_read().unwrap();
+    let mut line = String::new();
+    tmpfile.into_buf_reader().read_line(&mut line).unwrap();
+    assert_eq!(line, "ab\n");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_new_in_shm() {
+    let mut tmpfile = TempFile::new_in_shm_or_default().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    assert_eq!(tmpfile.read_all().unwrap(), b"abcde");
+    if std::path::Path::new("/dev/shm").is_dir() {
+        let tmpfile = TempFile::new_in_shm().unwrap();
+        assert_eq!(tmpfile.dir().unwrap(), std::path::Path::new("/dev/shm"));
+    }
+}
+
+#[test]
+#[cfg(feature = "memmap2")]
+fn test_as_mmap_mut() {
+    let mut tmpfile = TempFile::new().unwrap();
+    {
+        let mut map = unsafe { tmpfile.as_mmap_mut(5).unwrap() };
+        map.copy_from_slice(b"abcde");
+        map.flush().unwrap();
+    }
+    assert_eq!(tmpfile.read_all().unwrap(), b"abcde");
+}
+
+#[test]
+fn test_create_error() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let missing = dir.path().join("missing");
+    let err = TempFile::new_in(&missing).unwrap_err();
+    assert_eq!(err.dir, missing);
+    assert_eq!(err.error.kind(), std::io::ErrorKind::NotFound);
+    let err: std::io::Error = err.into();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
+
+#[test]
+fn test_fork() {
+    let mut tmpfile = TempFile::new().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    let mut copy = tmpfile.fork().unwrap();
+    assert_eq!(tmpfile.position().unwrap(), 5);
+    write!(tmpfile, "fgh").unwrap();
+    let mut buf = String::new();
+    copy.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_direct() {
+    use std::os::unix::io::AsRawFd;
+    // Not every filesystem supports O_DIRECT; only check that it's applied when it is.
+    if let Ok(tmpfile) = tempfile::Builder::new().direct(true).unnamed() {
+        let mut info = String::new();
+        std::fs::File::open(format!("/proc/self/fdinfo/{}", tmpfile.as_raw_fd())).unwrap()
+            .read_to_string(&mut info).unwrap();
+        let flags = info.lines().find(|l| l.starts_with("flags:")).unwrap();
+        let flags = u32::from_str_radix(flags["flags:".len()..].trim(), 8).unwrap();
+        // O_DIRECT is 0o40000 on most architectures but 0o200000 on ARM.
+        assert!(flags & (0o40000 | 0o200000) != 0);
+    }
+}
+
+#[test]
+fn test_drain() {
+    let mut tmpfile = TempFile::new().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    assert_eq!(tmpfile.drain().unwrap(), b"abcde");
+    assert_eq!(tmpfile.len().unwrap(), 0);
+    write!(tmpfile, "fg").unwrap();
+    assert_eq!(tmpfile.drain().unwrap(), b"fg");
+}
+
+#[test]
+fn test_into_named_in() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let mut tmpfile = TempFile::new().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    let mut named = tmpfile.into_named_in(dir.path()).unwrap();
+    assert_eq!(named.dir(), Some(dir.path()));
+    assert_eq!(named.position().unwrap(), 5);
+    let mut buf = String::new();
+    std::fs::File::open(named.path()).unwrap().read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+    let path = named.path().to_path_buf();
+    drop(named);
+    assert!(!path.exists());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_into_named_in_linkable() {
+    use std::os::unix::fs::MetadataExt;
+    let dir = tempfile::TempDir::new().unwrap();
+    let mut tmpfile = tempfile::Builder::new().linkable(true).unnamed_in(dir.path()).unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    let ino = tmpfile.as_file().metadata().unwrap().ino();
+    let named = tmpfile.into_named_in(dir.path()).unwrap();
+    // Linked, not copied.
+    assert_eq!(std::fs::metadata(named.path()).unwrap().ino(), ino);
+    assert_eq!(named.as_file().metadata().unwrap().ino(), ino);
+}
+
+#[test]
+fn test_with_contents() {
+    let mut tmpfile = TempFile::with_contents(b"abcde").unwrap();
+    let mut buf = String::new();
+    tmpfile.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+}
+
+#[test]
+fn test_is_anonymous() {
+    let tmpfile = TempFile::new().unwrap();
+    assert_eq!(tmpfile.is_anonymous(), cfg!(unix));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_set_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+    let tmpfile = TempFile::new().unwrap();
+    tmpfile.set_permissions(std::fs::Permissions::from_mode(0o640)).unwrap();
+    assert_eq!(tmpfile.metadata().unwrap().permissions().mode() & 0o777, 0o640);
+}
+
+#[test]
+fn test_flush_sync() {
+    let mut tmpfile = TempFile::new().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    tmpfile.flush_sync().unwrap();
+    assert_eq!(tmpfile.len().unwrap(), 5);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_into_owned_fd() {
+    use std::os::unix::io::OwnedFd;
+    let mut tmpfile = TempFile::new().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    let fd: OwnedFd = tmpfile.into_owned_fd();
+    let mut f = std::fs::File::from(fd);
+    f.seek(SeekFrom::Start(0)).unwrap();
+    let mut buf = String::new();
+    f.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+}
+
+#[test]
+fn test_hash() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::Hasher;
+    let mut a = TempFile::with_contents(b"abcde").unwrap();
+    let mut b = TempFile::with_contents(b"abcde").unwrap();
+    a.seek(SeekFrom::Start(2)).unwrap();
+    let (mut ha, mut hb) = (DefaultHasher::new(), DefaultHasher::new());
+    a.hash(&mut ha).unwrap();
+    b.hash(&mut hb).unwrap();
+    assert_eq!(ha.finish(), hb.finish());
+    assert_eq!(a.position().unwrap(), 2);
+}
+
+#[test]
+fn test_clone_to_dir() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let mut tmpfile = TempFile::with_contents(b"abcde").unwrap();
+    let mut copy = tmpfile.clone_to_dir(dir.path()).unwrap();
+    assert_eq!(copy.dir(), Some(dir.path()));
+    assert_eq!(tmpfile.position().unwrap(), 0);
+    let mut buf = String::new();
+    copy.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+}
+
+#[test]
+fn test_new_anonymous() {
+    let mut tmpfile = TempFile::new_anonymous().unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    assert_eq!(tmpfile.read_all().unwrap(), b"abcde");
+    assert_eq!(tmpfile.dir().is_none(), cfg!(target_os = "linux"));
+}
+
+#[test]
+fn test_large_file() {
+    const GIB: u64 = 1024 * 1024 * 1024;
+    let mut tmpfile = TempFile::new().unwrap();
+    // Sparse, so this doesn't actually use any space on most filesystems.
+    tmpfile.set_len(3 * GIB).unwrap();
+    assert_eq!(tmpfile.len().unwrap(), 3 * GIB);
+    assert_eq!(tmpfile.seek(SeekFrom::Start(5 * GIB)).unwrap(), 5 * GIB);
+    tmpfile.write_all(b"x").unwrap();
+    assert_eq!(tmpfile.len().unwrap(), 5 * GIB + 1);
+    tmpfile.seek(SeekFrom::Start(5 * GIB)).unwrap();
+    let mut buf = [0u8; 1];
+    tmpfile.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"x");
+}
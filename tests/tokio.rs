@@ -0,0 +1,18 @@
+#![cfg(feature = "tokio")]
+extern crate tempfile;
+extern crate tokio;
+
+use std::io::SeekFrom;
+use tempfile::tokio::AsyncTempFile;
+use tokio::io::{AsyncReadExt, AsyncWriteExt, AsyncSeekExt};
+
+#[test]
+fn test_basic() {
+    let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let mut tmpfile = rt.block_on(AsyncTempFile::new()).unwrap();
+    rt.block_on(tmpfile.write_all(b"abcde")).unwrap();
+    rt.block_on(tmpfile.seek(SeekFrom::Start(0))).unwrap();
+    let mut buf = String::new();
+    rt.block_on(tmpfile.read_to_string(&mut buf)).unwrap();
+    assert_eq!("abcde", buf);
+}
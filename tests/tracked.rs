@@ -0,0 +1,17 @@
+extern crate tempfile;
+use tempfile::NamedTempFile;
+
+// This lives in its own test binary since the registry is process-wide.
+#[test]
+fn test_cleanup_tracked() {
+    let tracked = NamedTempFile::new_tracked().unwrap();
+    let persisted = NamedTempFile::new_tracked().unwrap();
+    let untracked = NamedTempFile::new().unwrap();
+    let (_, kept_path) = persisted.keep().unwrap();
+
+    tempfile::cleanup_tracked();
+    assert!(!tracked.path().exists());
+    assert!(untracked.path().exists());
+    assert!(kept_path.exists());
+    std::fs::remove_file(kept_path).unwrap();
+}